@@ -0,0 +1,228 @@
+use std;
+use std::io::Write;
+use libc;
+use rubik2x2::ui::Console;
+use rubik2x2::ui::Input;
+
+pub struct ConsoleContext {
+    initial_attributes: libc::termios,
+    // The first error in reading or writing, after which the viewer is asked to exit, so that the terminal is
+    // restored before the error is reported.
+    optional_error: Option<ConsoleContextError>,
+}
+
+pub enum ConsoleContextInitializationError
+{
+    StandardInputIsNotATerminal,
+    FailedToGetTerminalAttributes{error_code: i32},
+    FailedToSetTerminalAttributes{error_code: i32},
+}
+
+pub enum ConsoleContextDeinitializationError
+{
+    FailedToResetTerminalAttributes{error_code: i32},
+}
+
+pub enum ConsoleContextError
+{
+    FailedToReadInput{error: std::io::Error},
+    FailedToWriteOutput{error: std::io::Error},
+}
+
+impl ConsoleContextError
+{
+    pub fn message(&self) -> String
+    {
+        use self::ConsoleContextError::*;
+        match self
+        {
+            FailedToReadInput{error} =>
+                format!("failed to read from the terminal: {}", error),
+            FailedToWriteOutput{error} =>
+                format!("failed to write to the terminal: {}", error),
+        }
+    }
+}
+
+impl ConsoleContextDeinitializationError
+{
+    pub fn message(&self) -> String
+    {
+        use self::ConsoleContextDeinitializationError::*;
+        match self
+        {
+            FailedToResetTerminalAttributes{error_code} =>
+                format!("failed to reset terminal attributes: error code: {}", error_code),
+        }
+    }
+}
+
+impl ConsoleContextInitializationError
+{
+    pub fn message(&self) -> String
+    {
+        use self::ConsoleContextInitializationError::*;
+        match *self
+        {
+            StandardInputIsNotATerminal =>
+                String::from("The standard input device is not a terminal"),
+            FailedToGetTerminalAttributes{error_code} =>
+                format!("Failed to get terminal attributes: error code: {}", error_code),
+            FailedToSetTerminalAttributes{error_code} =>
+                format!("Failed to set terminal attributes: error code: {}", error_code),
+        }
+    }
+}
+
+fn last_error_code() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+fn set_attributes(attributes: &libc::termios) -> Result<(), i32> {
+    let result = unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, attributes) };
+    if result != 0 {
+        return Err(last_error_code());
+    }
+    Ok(())
+}
+
+// Reads one byte from the standard input device, waiting for at most 'timeout_milliseconds', or indefinitely
+// if it is negative.
+fn read_byte(timeout_milliseconds: i32) -> Result<Option<u8>, std::io::Error> {
+    let mut poll_descriptor = libc::pollfd {fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0};
+    loop {
+        let result = unsafe { libc::poll(&mut poll_descriptor, 1, timeout_milliseconds) };
+        if result < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        if result == 0 {
+            return Ok(None);
+        }
+        break;
+    }
+
+    let mut byte = 0u8;
+    let result = unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if result == 0 {
+        // End of input: there will be no more keys.
+        return Ok(Some(0x04));
+    }
+    Ok(Some(byte))
+}
+
+impl Console for ConsoleContext {
+
+    fn wait_for_input(&mut self) -> Input {
+        match self.try_wait_for_input() {
+            Ok(input) => input,
+            Err(error) => {
+                self.optional_error = Some(ConsoleContextError::FailedToReadInput{error});
+                Input::Exit
+            },
+        }
+    }
+
+    fn show(&mut self, frame: &str) {
+        if self.optional_error.is_some() {
+            return;
+        }
+        // Erase the whole screen, and move the cursor to the top left corner.
+        let mut stdout = std::io::stdout().lock();
+        if let Err(error) = write!(stdout, "\x1b[2J\x1b[H{}", frame).and_then(|_| stdout.flush()) {
+            self.optional_error = Some(ConsoleContextError::FailedToWriteOutput{error});
+        }
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.optional_error.take().map(|error| error.message())
+    }
+}
+
+impl ConsoleContext {
+
+    fn try_wait_for_input(&mut self) -> Result<Input, std::io::Error> {
+        // How long to wait for the rest of an escape sequence, before deciding that the escape key was pressed.
+        let escape_sequence_timeout_milliseconds = 50;
+        loop {
+            match read_byte(-1)? {
+                // Ctrl-C and Ctrl-D, since signals are disabled in raw mode.
+                Some(0x03) | Some(0x04) => return Ok(Input::Exit),
+                Some(0x1b) => {
+                    let introducer = read_byte(escape_sequence_timeout_milliseconds)?;
+                    if introducer != Some(b'[') && introducer != Some(b'O') {
+                        return Ok(Input::Exit);
+                    }
+                    // Skip any parameters, up to the final byte of the sequence.
+                    let mut optional_final_byte = None;
+                    while let Some(byte) = read_byte(escape_sequence_timeout_milliseconds)? {
+                        if (0x40..=0x7e).contains(&byte) {
+                            optional_final_byte = Some(byte);
+                            break;
+                        }
+                    }
+                    match optional_final_byte {
+                        Some(b'C') => return Ok(Input::Forward),
+                        Some(b'D') => return Ok(Input::Back),
+                        _ => {},
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    pub fn try_initialize() -> Result<ConsoleContext, ConsoleContextInitializationError> {
+        use self::ConsoleContextInitializationError::*;
+        if unsafe { libc::isatty(libc::STDIN_FILENO) } == 0 {
+            return Err(StandardInputIsNotATerminal);
+        }
+
+        // Record in order to restore before exit
+        let initial_attributes = {
+            let mut attributes: libc::termios = unsafe { std::mem::zeroed() };
+            let result = unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut attributes) };
+            if result != 0 {
+                return Err(FailedToGetTerminalAttributes{error_code: last_error_code()});
+            }
+            attributes
+        };
+
+        // Also restore if we panic, since the shell would otherwise be left without echo.
+        {
+            let previous_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                let _ = set_attributes(&initial_attributes);
+                previous_hook(info);
+            }));
+        }
+
+        {
+            let mut attributes = initial_attributes;
+            attributes.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            attributes.c_iflag &= !(libc::IXON | libc::ICRNL);
+            attributes.c_cc[libc::VMIN] = 1;
+            attributes.c_cc[libc::VTIME] = 0;
+            if let Err(error_code) = set_attributes(&attributes) {
+                return Err(FailedToSetTerminalAttributes{error_code});
+            }
+        }
+
+        Ok(ConsoleContext {initial_attributes, optional_error: None})
+    }
+
+    pub fn try_deinitialize(&self) -> Option<ConsoleContextDeinitializationError> {
+        use self::ConsoleContextDeinitializationError::*;
+        // Restore the initial terminal attributes.
+        if let Err(error_code) = set_attributes(&self.initial_attributes) {
+            return Some(FailedToResetTerminalAttributes{error_code});
+        }
+        None
+    }
+}
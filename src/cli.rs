@@ -1,8 +1,13 @@
-use ::ui;
+use ::output;
 use std::collections::HashMap;
-use cube;
+use rubik2x2::cube;
+use rubik2x2::optimal;
+use rubik2x2::ui;
 use std;
 
+// Separates the configuration of the cube to solve from the configuration of the target cube.
+const TARGET_SEPARATOR: &str = "to";
+
 struct SideConfiguration
 {
     pub configuration: [[cube::Side; 4]; 6]
@@ -12,12 +17,90 @@ pub struct Input
 {
     pub aliases: ui::SideAliases,
     pub initial_cube: cube::Cube,
+    // If set, the solution turns the initial cube into this cube instead of solving it.
+    pub target_cube: Option<cube::Cube>,
+    pub metric: optimal::Metric,
+    pub solver: Solver,
+    // If set, the optimal solver looks up distances in this file instead of searching.
+    pub table_path: Option<String>,
+    // If set, the solution only turns these sides by these amounts.
+    pub move_set: Option<optimal::MoveSet>,
+    // If set, the cheapest solution with the move costs in this file is compared to the shortest solution.
+    pub costs_path: Option<String>,
+    // If set, the solution is printed in this format instead of being shown in the viewer.
+    pub output_format: Option<output::Format>,
+}
+
+// What to do, as selected by the first argument.
+pub enum Command
+{
+    // Solve a cube, which is the default when no command is given.
+    Solve(Box<Input>),
+    // Check the macro solver on every state.
+    VerifyMacroSolver,
+    // Find the distance of every state, and save them to a file.
+    BuildTable{metric: optimal::Metric, path: String},
+    // Count the states at each distance from the solved state, and print them in the given format, or as a
+    // table.
+    DepthDistribution{output_format: Option<output::Format>},
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solver
+{
+    // Searches for a shortest solution.
+    Optimal,
+    // Solves the positions and then the orientations of the cubies, using a fixed set of macros.
+    Macro,
+    // Solves the down side, then orients and permutes the layers, in named steps as people do.
+    Ortega,
+    // Solves the first layer, then orients and permutes the last layer, explaining each step.
+    Beginner,
+}
+
+impl Solver
+{
+    fn option(&self) -> &'static str
+    {
+        match *self
+        {
+            Solver::Optimal => "--solver=optimal",
+            Solver::Macro => "--solver=macro",
+            Solver::Ortega => "--solver=ortega",
+            Solver::Beginner => "--solver=beginner",
+        }
+    }
+}
+
+struct Options
+{
+    metric: optimal::Metric,
+    solver: Solver,
+    table_path: Option<String>,
+    move_set: Option<optimal::MoveSet>,
+    costs_path: Option<String>,
+    scramble: Option<cube::Macro>,
+    target_scramble: Option<cube::Macro>,
+    output_format: Option<output::Format>,
+    // The options that were given, without their values, such as "--metric".
+    option_names: Vec<String>,
 }
 
 pub enum ArgumentReadingError
 {
+    UnknownOption{argument: String},
+    InvalidScramble{notation_error: cube::NotationError},
+    InvalidGenerators{argument: String},
+    InvalidTurns{argument: String},
+    ConflictingOptions{option: String, other_option: String},
+    UnexpectedArgument{argument: String},
+    UnexpectedCommandArgument{command: String, argument: String},
+    UnexpectedCommandOption{command: String, option: String},
+    MissingCommandArgument{command: String, argument_description: String},
+    MissingTargetConfiguration,
     InvalidAliasArguments{side_alias_error: SideAliasReadingError},
     InvalidCubeConfiguration{configuration_error: SideConfigurationError},
+    UnsolvableCubeConfiguration{configuration_error: SideConfigurationError},
 }
 
 pub enum SideConfigurationError
@@ -26,6 +109,13 @@ pub enum SideConfigurationError
     MissingOpeningCurlyBracket,
     MissingClosingCurlyBracket,
     InvalidSideAlias{expected_alias: String},
+    // In the following, 'corner' lists the sides that meet at the corner position, and 'stickers' lists the
+    // aliases of the stickers on those sides.
+    RepeatedStickerOnCorner{corner: [cube::Side; 3], stickers: [String; 3]},
+    OppositeStickersOnCorner{corner: [cube::Side; 3], stickers: [String; 3]},
+    MirroredCorner{corner: [cube::Side; 3], stickers: [String; 3]},
+    DuplicatedCorner{corner: [cube::Side; 3], other_corner: [cube::Side; 3], stickers: [String; 3]},
+    TwistedCorners{twist: u8},
 }
 
 pub enum SideAliasReadingError
@@ -77,15 +167,51 @@ impl SideAliasReadingError
 
 impl ArgumentReadingError
 {
+    // Whether the arguments were well-formed, but describe a cube that can not be solved.
+    pub fn is_unsolvable(&self) -> bool
+    {
+        matches!(self, &ArgumentReadingError::UnsolvableCubeConfiguration{..})
+    }
+
     pub fn message(&self) -> String
     {
         use self::ArgumentReadingError::*;
         match self
         {
+            &UnknownOption{ref argument} =>
+                format!("Unknown option: {}\n\
+                         Valid options are: --metric=half-turn, --metric=quarter-turn, --solver=optimal, \
+                         --solver=macro, --solver=ortega, --solver=beginner, --table=[file], --costs=[file], \
+                         --generators=[sides], --turns=[amounts], --scramble=[moves], --target=[moves], \
+                         --output=plain, --output=lines, --output=json, --output=csv\n",
+                        argument),
+            &InvalidScramble{ref notation_error} =>
+                format!("Invalid scramble: {}", notation_error.message()),
+            &InvalidGenerators{ref argument} =>
+                format!("Invalid generators: {}: expected the letters of the sides to turn, such as RUF", argument),
+            &InvalidTurns{ref argument} =>
+                format!("Invalid turns: {}: expected a comma-separated list of clockwise, counter-clockwise, \
+                         half and quarter", argument),
+            &ConflictingOptions{ref option, ref other_option} =>
+                format!("Conflicting options: {} can not be combined with {}", option, other_option),
+            &UnexpectedArgument{ref argument} =>
+                format!("Unexpected argument: {}: a scrambled cube takes no side configuration arguments",
+                        argument),
+            &UnexpectedCommandArgument{ref command, ref argument} =>
+                format!("Unexpected argument: {}: the '{}' command takes no arguments", argument, command),
+            &UnexpectedCommandOption{ref command, ref option} =>
+                format!("Unexpected option: {}: the '{}' command does not use it", option, command),
+            &MissingCommandArgument{ref command, ref argument_description} =>
+                format!("Missing argument: the '{}' command takes {}", command, argument_description),
+            &MissingTargetConfiguration =>
+                format!("Missing target configuration: '{}' should be followed by the sides of the target cube",
+                        TARGET_SEPARATOR),
             &InvalidAliasArguments{ref side_alias_error} =>
                 format!("Invalid alias arguments: {}", side_alias_error.message()),
             &InvalidCubeConfiguration{ref configuration_error} =>
                 format!("Invalid cube configuration: {}", configuration_error.message()),
+            &UnsolvableCubeConfiguration{ref configuration_error} =>
+                format!("Unsolvable cube configuration: {}", configuration_error.message()),
         }
     }
 }
@@ -110,84 +236,478 @@ impl SideConfigurationError
                          Here, [side] may be one of the following: {}\n", argument, allowed_sides)
             },
             &MissingOpeningCurlyBracket =>
-                format!("Invalid configuration: expected '{{'\n"),
+                String::from("Invalid configuration: expected '{'\n"),
             &MissingClosingCurlyBracket =>
-                format!("Invalid configuration: last alias should be followed by a '}}'"),
+                String::from("Invalid configuration: last alias should be followed by a '}'"),
             &InvalidSideAlias{ref expected_alias} => 
                 format!("Invalid configuration: invalid alias: '{}'", expected_alias),
+            &RepeatedStickerOnCorner{corner, ref stickers} =>
+                format!("Invalid configuration: {}: a corner can not have two stickers of the same color",
+                        corner_description(corner, stickers)),
+            &OppositeStickersOnCorner{corner, ref stickers} =>
+                format!("Invalid configuration: {}: a corner can not have stickers from opposite sides",
+                        corner_description(corner, stickers)),
+            &MirroredCorner{corner, ref stickers} =>
+                format!("Invalid configuration: {}: the stickers are in mirrored order",
+                        corner_description(corner, stickers)),
+            &DuplicatedCorner{corner, other_corner, ref stickers} =>
+                format!("Invalid configuration: {}: the corner at {} has the same stickers",
+                        corner_description(corner, stickers), corner_sides_description(other_corner)),
+            &TwistedCorners{twist} =>
+                format!("Invalid configuration: the corners are twisted by a total of {}/3 of a turn, \
+                         so one corner needs to be twisted by {}/3 of a turn to make the cube solvable",
+                        twist, 3 - twist),
         }
     }
 }
 
+fn corner_sides_description(corner: [cube::Side; 3]) -> String
+{
+    format!("{}-{}-{}",
+            cube::Side::serialization(corner[0]),
+            cube::Side::serialization(corner[1]),
+            cube::Side::serialization(corner[2]))
+}
+
+fn corner_description(corner: [cube::Side; 3], stickers: &[String; 3]) -> String
+{
+    format!("the corner at {} has stickers {}, {}, {}",
+            corner_sides_description(corner), stickers[0], stickers[1], stickers[2])
+}
+
 impl SideConfiguration {
     pub fn to_cube(&self) -> cube::Cube {
-        let configurations: &[[cube::Side; 4]; 6] = &self.configuration;
-        let mut transforms = [[[cube::Transform::identity(); 2]; 2]; 2];
-        for side_idx in 0..6 {
-            for entry_idx in 0..4 {
-                let d0 = side_idx/2;
-                let d1 = [1, 0, 0][d0];
-                let d2 = [2, 2, 1][d0];
-                let i0 = side_idx & 1;
-                let (i1, i2) = ((entry_idx >> 0) & 1, (entry_idx >> 1) & 1);
-                let mut index = [0; 3];
-                index[d0] = i0;
-                index[d1] = i1;
-                index[d2] = i2;
-                let side = cube::Side::from(side_idx as i32);
-                let solved_side = configurations[side_idx][entry_idx];
-                let side_normal = cube::normal(side);
-                let solved_side_normal = cube::normal(solved_side);
-                let column_idx = {
-                    let mut idx = 0;
-                    while idx < 3 {
-                        if solved_side_normal[idx] != 0 {
-                            break;
+        cube::Cube::from_stickers(&self.configuration)
+    }
+
+    // The sides that meet at the corner position 'position_idx', and the stickers on them, in x, y, z order.
+    fn corner(&self, position_idx: usize) -> ([cube::Side; 3], [cube::Side; 3]) {
+        let mut sides = [cube::Side::L; 3];
+        let mut stickers = [cube::Side::L; 3];
+        for d0 in 0..3 {
+            let d1 = [1, 0, 0][d0];
+            let d2 = [2, 2, 1][d0];
+            let side_idx = 2*d0 + ((position_idx >> d0) & 1);
+            let entry_idx = ((position_idx >> d1) & 1) | (((position_idx >> d2) & 1) << 1);
+            sides[d0] = cube::Side::from(side_idx as i32);
+            stickers[d0] = self.configuration[side_idx][entry_idx];
+        }
+        (sides, stickers)
+    }
+
+    // Checks that the configuration can be reached by turning the sides of a solved cube.
+    fn try_validate(&self, side_aliases: &ui::SideAliases) -> Result<(), SideConfigurationError> {
+        use self::SideConfigurationError::*;
+        let sticker_aliases = |stickers: [cube::Side; 3]| {
+            [side_aliases.alias(stickers[0]), side_aliases.alias(stickers[1]), side_aliases.alias(stickers[2])]
+        };
+
+        // The position where the cubie at each position belongs.
+        let mut solved_position_idxs = [0; 8];
+        for position_idx in 0..8 {
+            let (corner, stickers) = self.corner(position_idx);
+            for (i, j) in [(0, 1), (0, 2), (1, 2)].iter() {
+                if stickers[*i] == stickers[*j] {
+                    return Err(RepeatedStickerOnCorner{corner, stickers: sticker_aliases(stickers)});
+                }
+                if (stickers[*i] as usize)/2 == (stickers[*j] as usize)/2 {
+                    return Err(OppositeStickersOnCorner{corner, stickers: sticker_aliases(stickers)});
+                }
+            }
+            for sticker in stickers.iter() {
+                solved_position_idxs[position_idx] |= ((*sticker as usize) & 1) << ((*sticker as usize)/2);
+            }
+        }
+
+        let cube = self.to_cube();
+        for position_idx in 0..8 {
+            let (corner, stickers) = self.corner(position_idx);
+            let position = [
+                cube::normal(corner[0])[0],
+                cube::normal(corner[1])[1],
+                cube::normal(corner[2])[2],
+            ];
+            if cube.transform(position).determinant() != 1 {
+                return Err(MirroredCorner{corner, stickers: sticker_aliases(stickers)});
+            }
+            for other_position_idx in 0..position_idx {
+                if solved_position_idxs[other_position_idx] == solved_position_idxs[position_idx] {
+                    return Err(
+                        DuplicatedCorner{
+                            corner,
+                            other_corner: self.corner(other_position_idx).0,
+                            stickers: sticker_aliases(stickers),
                         }
-                        assert!(solved_side_normal[idx] == 0);
-                        idx += 1;
-                    }
-                    assert!(idx < 3);
-                    idx
-                };
-                for row_idx in 0..3 {
-                    transforms[index[0]][index[1]][index[2]].entries[row_idx][column_idx] =
-                        solved_side_normal[column_idx]*side_normal[row_idx];
+                    );
                 }
             }
         }
 
-        cube::Cube {transforms: transforms}
+        let (_, orientations) = cube.positions_orientations();
+        let twist = orientations.iter().fold(0, |sum, o| (sum + o) % 3);
+        if twist != 0 {
+            return Err(TwistedCorners{twist});
+        }
+        return Ok(());
     }
-    
 }
 
-pub fn try_read_arguments<I>(
+pub fn try_read_command<I>(
+    argument_iterator: &mut I
+) -> Result<Command, ArgumentReadingError>
+where
+    I: Iterator<Item = String>
+{
+    use self::ArgumentReadingError::*;
+    let mut argument_iterator = argument_iterator.peekable();
+    // Commands are plain words, unlike options and the other arguments.
+    let is_command =
+        matches!(
+            argument_iterator.peek().map(|argument| argument.as_str()),
+            Some("verify") | Some("build-table") | Some("depths")
+        );
+    if !is_command {
+        match try_read_arguments(&mut argument_iterator)
+        {
+            Err(error) => return Err(error),
+            Ok(input) => return Ok(Command::Solve(Box::new(input))),
+        }
+    }
+
+    let command = argument_iterator.next().unwrap();
+    let options =
+    {
+        match try_read_option_arguments(&mut argument_iterator)
+        {
+            Err(error) => return Err(error),
+            Ok(options) => options,
+        }
+    };
+    let optional_path = argument_iterator.next();
+    if let Some(argument) = argument_iterator.next() {
+        return Err(UnexpectedCommandArgument{command, argument});
+    }
+    let command_option_names: &[&str] =
+    {
+        match command.as_str()
+        {
+            "build-table" => &["--metric"],
+            "depths" => &["--output"],
+            _ => &[],
+        }
+    };
+    if let Some(option) = options.option_names.iter().find(|name| !command_option_names.contains(&name.as_str())) {
+        return Err(UnexpectedCommandOption{command, option: option.clone()});
+    }
+    match command.as_str()
+    {
+        "verify" => {
+            if let Some(argument) = optional_path {
+                return Err(UnexpectedCommandArgument{command, argument});
+            }
+            return Ok(Command::VerifyMacroSolver);
+        },
+        "depths" => {
+            if let Some(argument) = optional_path {
+                return Err(UnexpectedCommandArgument{command, argument});
+            }
+            return Ok(Command::DepthDistribution{output_format: options.output_format});
+        },
+        "build-table" => {
+            match optional_path
+            {
+                None => {
+                    let argument_description = String::from("the path of the file to write the table to");
+                    return Err(MissingCommandArgument{command, argument_description});
+                },
+                Some(path) => return Ok(Command::BuildTable{metric: options.metric, path}),
+            }
+        },
+        _ => panic!(),
+    }
+}
+
+fn try_read_arguments<I>(
     argument_iterator: &mut I
 ) -> Result<Input, ArgumentReadingError>
 where
     I: Iterator<Item = String>
 {
     use self::ArgumentReadingError::*;
+    let mut argument_iterator = argument_iterator.peekable();
+    let options =
+    {
+        match try_read_option_arguments(&mut argument_iterator)
+        {
+            Err(error) => return Err(error),
+            Ok(options) => options,
+        }
+    };
+
+    if let Some(scramble) = options.scramble {
+        // The aliases are optional here, since they are only used for showing the cube.
+        let side_aliases =
+        {
+            if argument_iterator.peek().is_none() {
+                ui::SideAliases::side_initials()
+            } else {
+                match try_read_side_alias_arguments(&mut argument_iterator)
+                {
+                    Err(error) => return Err(InvalidAliasArguments{side_alias_error: error}),
+                    Ok(side_aliases) => side_aliases
+                }
+            }
+        };
+        if let Some(argument) = argument_iterator.next() {
+            return Err(UnexpectedArgument{argument});
+        }
+        let solved_cube = cube::Cube::solved();
+        return Ok(
+            Input {
+                aliases: side_aliases,
+                initial_cube: solved_cube.sequence_moves(scramble.move_iter()),
+                target_cube: options.target_scramble.map(|target| solved_cube.sequence_moves(target.move_iter())),
+                metric: options.metric,
+                solver: options.solver,
+                table_path: options.table_path,
+                move_set: options.move_set,
+                costs_path: options.costs_path,
+                output_format: options.output_format,
+            }
+        );
+    }
+
     let side_aliases =
     {
-        match try_read_side_alias_arguments(argument_iterator)
+        match try_read_side_alias_arguments(&mut argument_iterator)
         {
             Err(error) => return Err(InvalidAliasArguments{side_alias_error: error}),
             Ok(side_aliases) => side_aliases
         }
     };
     
+    // A second configuration, after the separator, describes the target cube.
+    let configuration_arguments: Vec<String> = argument_iterator.collect();
+    let (start_arguments, optional_target_arguments) =
+    {
+        match configuration_arguments.iter().position(|argument| argument == TARGET_SEPARATOR)
+        {
+            Some(idx) => (&configuration_arguments[..idx], Some(&configuration_arguments[idx + 1..])),
+            None => (&configuration_arguments[..], None),
+        }
+    };
+
     let configuration =
         {
-            match try_read_side_configuration_arguments(&side_aliases, argument_iterator)
+            match try_read_side_configuration_arguments(&side_aliases, &mut start_arguments.iter().cloned())
             {
                 Err(error) => return Err(InvalidCubeConfiguration{configuration_error: error}),
                 Ok(configuration) => configuration,
             }
         };
 
-    return Ok(Input{aliases: side_aliases, initial_cube: configuration.to_cube()});
+    if let Err(error) = configuration.try_validate(&side_aliases) {
+        return Err(UnsolvableCubeConfiguration{configuration_error: error});
+    }
+
+    let mut target_cube = options.target_scramble.as_ref().map(|target| {
+        cube::Cube::solved().sequence_moves(target.move_iter())
+    });
+    if let Some(target_arguments) = optional_target_arguments {
+        if target_arguments.is_empty() {
+            return Err(MissingTargetConfiguration);
+        }
+        if target_cube.is_some() {
+            let option = String::from("--target");
+            return Err(ConflictingOptions{option, other_option: String::from("a target configuration")});
+        }
+        if let Err(error) = check_target_options(&options, "a target configuration") {
+            return Err(error);
+        }
+        let target_configuration =
+        {
+            match try_read_side_configuration_arguments(&side_aliases, &mut target_arguments.iter().cloned())
+            {
+                Err(error) => return Err(InvalidCubeConfiguration{configuration_error: error}),
+                Ok(target_configuration) => target_configuration,
+            }
+        };
+        if let Err(error) = target_configuration.try_validate(&side_aliases) {
+            return Err(UnsolvableCubeConfiguration{configuration_error: error});
+        }
+        target_cube = Some(target_configuration.to_cube());
+    }
+
+    return Ok(
+        Input {
+            aliases: side_aliases,
+            initial_cube: configuration.to_cube(),
+            target_cube: target_cube,
+            metric: options.metric,
+            solver: options.solver,
+            table_path: options.table_path,
+            move_set: options.move_set,
+            costs_path: options.costs_path,
+            output_format: options.output_format,
+        }
+    );
+}
+
+// Options precede the alias arguments, and all start with "--".
+fn try_read_option_arguments<I>(
+    argument_iterator: &mut std::iter::Peekable<I>
+) -> Result<Options, ArgumentReadingError>
+where
+    I: Iterator<Item = String>
+{
+    use self::ArgumentReadingError::*;
+    let mut options = Options{
+        metric: optimal::Metric::HalfTurn,
+        solver: Solver::Optimal,
+        table_path: None,
+        move_set: None,
+        costs_path: None,
+        scramble: None,
+        target_scramble: None,
+        output_format: None,
+        option_names: Vec::new(),
+    };
+    let mut generator_sides = None;
+    let mut generator_turns = None;
+    while argument_iterator.peek().is_some_and(|argument| argument.starts_with("--")) {
+        let argument = argument_iterator.next().unwrap();
+        options.option_names.push(String::from(argument.split('=').next().unwrap()));
+        if let Some(scramble_notation) = argument.strip_prefix("--scramble=") {
+            match scramble_notation.parse()
+            {
+                Err(notation_error) => return Err(InvalidScramble{notation_error}),
+                Ok(scramble) => options.scramble = Some(scramble),
+            }
+            continue;
+        }
+        if let Some(table_path) = argument.strip_prefix("--table=") {
+            options.table_path = Some(String::from(table_path));
+            continue;
+        }
+        if let Some(target_notation) = argument.strip_prefix("--target=") {
+            match target_notation.parse()
+            {
+                Err(notation_error) => return Err(InvalidScramble{notation_error}),
+                Ok(target_scramble) => options.target_scramble = Some(target_scramble),
+            }
+            continue;
+        }
+        if let Some(costs_path) = argument.strip_prefix("--costs=") {
+            options.costs_path = Some(String::from(costs_path));
+            continue;
+        }
+        if let Some(side_letters) = argument.strip_prefix("--generators=") {
+            let optional_sides: Option<Vec<cube::Side>> = side_letters.chars().map(cube::Side::from_letter).collect();
+            match optional_sides
+            {
+                Some(ref sides) if !sides.is_empty() => generator_sides = optional_sides,
+                _ => return Err(InvalidGenerators{argument}),
+            }
+            continue;
+        }
+        if let Some(turn_names) = argument.strip_prefix("--turns=") {
+            let mut turns = Vec::new();
+            for turn_name in turn_names.split(',') {
+                match turn_name
+                {
+                    "clockwise" => turns.push(optimal::Turn::Clockwise),
+                    "counter-clockwise" => turns.push(optimal::Turn::CounterClockwise),
+                    "half" => turns.push(optimal::Turn::Half),
+                    "quarter" => turns.extend_from_slice(&[optimal::Turn::CounterClockwise, optimal::Turn::Clockwise]),
+                    _ => return Err(InvalidTurns{argument}),
+                }
+            }
+            generator_turns = Some(turns);
+            continue;
+        }
+        match argument.as_str() {
+            "--metric=half-turn" => options.metric = optimal::Metric::HalfTurn,
+            "--metric=quarter-turn" => options.metric = optimal::Metric::QuarterTurn,
+            "--solver=optimal" => options.solver = Solver::Optimal,
+            "--solver=macro" => options.solver = Solver::Macro,
+            "--solver=ortega" => options.solver = Solver::Ortega,
+            "--solver=beginner" => options.solver = Solver::Beginner,
+            "--output=plain" => options.output_format = Some(output::Format::Plain),
+            "--output=lines" => options.output_format = Some(output::Format::Lines),
+            "--output=json" => options.output_format = Some(output::Format::Json),
+            "--output=csv" => options.output_format = Some(output::Format::Csv),
+            _ => return Err(UnknownOption{argument}),
+        }
+    }
+    // Only searching for an optimal solution depends on the metric, or looks up distances.
+    if options.solver != Solver::Optimal {
+        let other_option = String::from(options.solver.option());
+        if options.costs_path.is_some() {
+            return Err(ConflictingOptions{option: String::from("--costs"), other_option});
+        }
+        if options.table_path.is_some() {
+            return Err(ConflictingOptions{option: String::from("--table"), other_option});
+        }
+        if options.option_names.iter().any(|name| name == "--metric") {
+            return Err(ConflictingOptions{option: String::from("--metric"), other_option});
+        }
+    }
+    if generator_sides.is_some() || generator_turns.is_some() {
+        let option = String::from(if generator_sides.is_some() {"--generators"} else {"--turns"});
+        // Restricted solutions are found by searching.
+        if options.solver != Solver::Optimal {
+            return Err(ConflictingOptions{option, other_option: String::from(options.solver.option())});
+        }
+        if options.table_path.is_some() {
+            return Err(ConflictingOptions{option, other_option: String::from("--table")});
+        }
+        if options.costs_path.is_some() {
+            return Err(ConflictingOptions{option, other_option: String::from("--costs")});
+        }
+        // Without one of the options, all sides are turned, or sides are turned by all amounts.
+        let all_sides = (0..6).map(cube::Side::from).collect();
+        let all_turns = vec![optimal::Turn::CounterClockwise, optimal::Turn::Half, optimal::Turn::Clockwise];
+        options.move_set = Some(
+            optimal::MoveSet {
+                sides: generator_sides.unwrap_or(all_sides),
+                turns: generator_turns.unwrap_or(all_turns),
+            }
+        );
+    }
+    if options.target_scramble.is_some() {
+        if let Err(error) = check_target_options(&options, "--target") {
+            return Err(error);
+        }
+    }
+    return Ok(options);
+}
+
+// Checks that the options can be used for turning the cube into a target cube, which 'option' asks for.
+fn check_target_options(options: &Options, option: &str) -> Result<(), ArgumentReadingError>
+{
+    let optional_other_option =
+    {
+        if options.solver != Solver::Optimal {
+            Some(options.solver.option())
+        } else if options.table_path.is_some() {
+            Some("--table")
+        } else if options.move_set.is_some() {
+            Some("--generators")
+        } else if options.costs_path.is_some() {
+            Some("--costs")
+        } else {
+            None
+        }
+    };
+    match optional_other_option
+    {
+        Some(other_option) =>
+            Err(ArgumentReadingError::ConflictingOptions{
+                option: String::from(option),
+                other_option: String::from(other_option)
+            }),
+        None => Ok(()),
+    }
 }
 
 fn try_read_side_alias_arguments<I>(argument_iterator: &mut I) -> Result<ui::SideAliases, SideAliasReadingError>
@@ -206,13 +726,11 @@ where
         };
         let (alias, side) = {
             let mut pieces = [""; 2];
-            let mut piece_count = 0;
-            for piece in argument.split("=") {
+            for (piece_count, piece) in argument.split("=").enumerate() {
                 if piece_count >= 2 {
                     return Err(TooManyEqualsSigns{argument: argument.clone()})
                 }
                 pieces[piece_count] = piece;
-                piece_count += 1;
             }
             let alias: &str = pieces[0];
             if let Some(&side) = side_aliases.get(alias) {
@@ -249,16 +767,15 @@ where
     use self::SideConfigurationError::*;
     let mut side_configurations: [[cube::Side; 4]; 6] = [[cube::Side::L; 4]; 6];
     
-    while let Some(argument) = argument_iterator.next() {
+    for argument in argument_iterator.by_ref() {
         let mut rest = argument.as_str();
         let side = {
             let mut try_read_side = || {
                 for side_idx in 0..6 {
                     let side = cube::Side::from(side_idx);
                     let serialization = cube::Side::serialization(side);
-                    let serialization_length = serialization.len();
-                    if rest[0..serialization_length] == *serialization {
-                        rest = &rest[serialization_length..];
+                    if rest.starts_with(serialization) {
+                        rest = &rest[serialization.len()..];
                         return Ok(side);
                     }
                 }
@@ -280,7 +797,7 @@ where
         
         let configuration = {
 
-            let mut configuration: [cube::Side; 4] = unsafe { std::mem::uninitialized() };
+            let mut configuration: [cube::Side; 4] = [cube::Side::L; 4];
             for i in 0..4 {
                 let c = if i == 3 {'}'} else {','};
                 let optional_c_idx = rest.find(c);
@@ -305,8 +822,347 @@ where
 
             configuration
         };
-        side_configurations[side as usize] = configuration.clone();
+        side_configurations[side as usize] = configuration;
     }
 
     return Ok(SideConfiguration{configuration: side_configurations});
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // The arguments that describe 'cube', with the sides aliased by their initials.
+    fn configuration_arguments(cube: &cube::Cube) -> Vec<String>
+    {
+        let initial = |side| String::from(&cube::Side::serialization(side)[0..1]);
+        let mut arguments = Vec::new();
+        for side_idx in 0..6 {
+            let side = cube::Side::from(side_idx);
+            arguments.push(format!("{}={}", initial(side), cube::Side::serialization(side)));
+        }
+        for side_idx in 0..6 {
+            let side = cube::Side::from(side_idx as i32);
+            let mut stickers = Vec::new();
+            for entry_idx in 0..4i32 {
+                let d0 = side_idx/2;
+                let mut position = [0; 3];
+                position[d0] = 2*((side_idx & 1) as i32) - 1;
+                position[[1, 0, 0][d0]] = 2*(entry_idx & 1) - 1;
+                position[[2, 2, 1][d0]] = 2*((entry_idx >> 1) & 1) - 1;
+                let solved_normal = cube.transform(position).inverse().apply(&cube::normal(side));
+                stickers.push(initial(cube::normal_side(solved_normal)));
+            }
+            arguments.push(format!("{}{{{}}}", cube::Side::serialization(side), stickers.join(",")));
+        }
+        arguments
+    }
+
+    fn configuration_error(arguments: Vec<String>) -> Option<SideConfigurationError>
+    {
+        match try_read_arguments(&mut arguments.into_iter())
+        {
+            Err(ArgumentReadingError::UnsolvableCubeConfiguration{configuration_error}) => Some(configuration_error),
+            Err(error) => panic!("{}", error.message()),
+            Ok(_) => None,
+        }
+    }
+
+    fn reading_error(arguments: &[&str]) -> ArgumentReadingError
+    {
+        match try_read_arguments(&mut arguments.iter().map(|&argument| String::from(argument)))
+        {
+            Err(error) => error,
+            Ok(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn invalid_options_are_rejected()
+    {
+        match reading_error(&["--metric=slice-turn"]) {
+            ArgumentReadingError::UnknownOption{argument} => assert_eq!(argument, "--metric=slice-turn"),
+            _ => panic!(),
+        }
+        match reading_error(&["--scramble=R U X"]) {
+            ArgumentReadingError::InvalidScramble{notation_error} =>
+                assert_eq!(notation_error, cube::NotationError::InvalidMove{column: 5, token: String::from("X")}),
+            _ => panic!(),
+        }
+        let aliases = ["l=left", "r=right", "d=down", "u=up", "b=back", "f=front"];
+        match reading_error(&[&["--scramble=R U"], &aliases[..], &["front{f,f,f,f}"]].concat()) {
+            ArgumentReadingError::UnexpectedArgument{argument} => assert_eq!(argument, "front{f,f,f,f}"),
+            _ => panic!(),
+        }
+        assert!(!reading_error(&["--output=xml"]).is_unsolvable());
+        match reading_error(&["--generators=RUX"]) {
+            ArgumentReadingError::InvalidGenerators{argument} => assert_eq!(argument, "--generators=RUX"),
+            _ => panic!(),
+        }
+        match reading_error(&["--turns=quarter,sideways"]) {
+            ArgumentReadingError::InvalidTurns{argument} => assert_eq!(argument, "--turns=quarter,sideways"),
+            _ => panic!(),
+        }
+        match reading_error(&["--turns=half", "--table=distances.table"]) {
+            ArgumentReadingError::ConflictingOptions{option, other_option} =>
+                assert!(option == "--turns" && other_option == "--table"),
+            _ => panic!(),
+        }
+        match reading_error(&["--solver=ortega", "--generators=RU"]) {
+            ArgumentReadingError::ConflictingOptions{option, other_option} =>
+                assert!(option == "--generators" && other_option == "--solver=ortega"),
+            _ => panic!(),
+        }
+        match reading_error(&["--solver=macro", "--metric=quarter-turn"]) {
+            ArgumentReadingError::ConflictingOptions{option, other_option} =>
+                assert!(option == "--metric" && other_option == "--solver=macro"),
+            _ => panic!(),
+        }
+        match reading_error(&["--table=distances.table", "--solver=beginner"]) {
+            ArgumentReadingError::ConflictingOptions{option, other_option} =>
+                assert!(option == "--table" && other_option == "--solver=beginner"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn reads_commands()
+    {
+        let read_command = |arguments: &[&str]| {
+            try_read_command(&mut arguments.iter().map(|&argument| String::from(argument)))
+        };
+        match read_command(&["depths", "--output=csv"]) {
+            Ok(Command::DepthDistribution{output_format}) => assert_eq!(output_format, Some(output::Format::Csv)),
+            _ => panic!(),
+        }
+        match read_command(&["build-table", "--metric=quarter-turn", "distances.table"]) {
+            Ok(Command::BuildTable{metric, path}) =>
+                assert!(metric == optimal::Metric::QuarterTurn && path == "distances.table"),
+            _ => panic!(),
+        }
+        match read_command(&["build-table"]) {
+            Err(ArgumentReadingError::MissingCommandArgument{command, ..}) => assert_eq!(command, "build-table"),
+            _ => panic!(),
+        }
+        match read_command(&["depths", "extra"]) {
+            Err(ArgumentReadingError::UnexpectedCommandArgument{argument, ..}) => assert_eq!(argument, "extra"),
+            _ => panic!(),
+        }
+        match read_command(&["depths", "--metric=quarter-turn"]) {
+            Err(ArgumentReadingError::UnexpectedCommandOption{command, option}) =>
+                assert!(command == "depths" && option == "--metric"),
+            _ => panic!(),
+        }
+        match read_command(&["verify", "--output=json"]) {
+            Err(ArgumentReadingError::UnexpectedCommandOption{command, option}) =>
+                assert!(command == "verify" && option == "--output"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn reads_target_configuration()
+    {
+        let start = cube::Cube::solved().sequence_moves("R U F'".parse::<cube::Macro>().unwrap().move_iter());
+        let target_scramble = "R U R' U R U2 R'".parse::<cube::Macro>().unwrap();
+        let target = cube::Cube::solved().sequence_moves(target_scramble.move_iter());
+        let mut arguments = configuration_arguments(&start);
+        arguments.push(String::from("to"));
+        // The aliases are only given once.
+        arguments.extend_from_slice(&configuration_arguments(&target)[6..]);
+        match try_read_arguments(&mut arguments.clone().into_iter())
+        {
+            Ok(input) => assert!(input.initial_cube == start && input.target_cube == Some(target)),
+            Err(error) => panic!("{}", error.message()),
+        }
+
+        arguments.truncate(13);
+        match try_read_arguments(&mut arguments.into_iter())
+        {
+            Err(ArgumentReadingError::MissingTargetConfiguration) => {},
+            _ => panic!(),
+        }
+        match reading_error(&["--target=R", "--solver=macro"]) {
+            ArgumentReadingError::ConflictingOptions{option, ..} => assert_eq!(option, "--target"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn invalid_aliases_are_rejected()
+    {
+        use self::SideAliasReadingError::*;
+        let alias_error = |arguments: &[&str]| {
+            match reading_error(arguments) {
+                ArgumentReadingError::InvalidAliasArguments{side_alias_error} => side_alias_error,
+                _ => panic!(),
+            }
+        };
+        match alias_error(&["l=left", "r=right"]) {
+            TooFewAliasesSpecified{specified_alias_count} => assert_eq!(specified_alias_count, 2),
+            _ => panic!(),
+        }
+        match alias_error(&["l=left=right"]) {
+            TooManyEqualsSigns{argument} => assert_eq!(argument, "l=left=right"),
+            _ => panic!(),
+        }
+        match alias_error(&["l=left", "l=right"]) {
+            AmbiguousAlias{alias, side} => assert!(alias == "l" && side == cube::Side::L),
+            _ => panic!(),
+        }
+        match alias_error(&["l=sideways"]) {
+            InvalidSideSpecifier{argument} => assert_eq!(argument, "l=sideways"),
+            _ => panic!(),
+        }
+        match alias_error(&["l=left", "m=left"]) {
+            MultipleAliasesForSameSide{side} => assert!(side == cube::Side::L),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn malformed_configuration_is_rejected()
+    {
+        use self::SideConfigurationError::*;
+        let mut arguments = configuration_arguments(&cube::Cube::solved());
+        let mut configuration_error = |argument: &str| {
+            arguments[6] = String::from(argument);
+            match try_read_arguments(&mut arguments.clone().into_iter()) {
+                Err(ArgumentReadingError::InvalidCubeConfiguration{configuration_error}) => configuration_error,
+                _ => panic!(),
+            }
+        };
+        match configuration_error("up") {
+            MissingOpeningCurlyBracket => {},
+            _ => panic!(),
+        }
+        // Shorter than any side name.
+        match configuration_error("l") {
+            InvalidSide{argument} => assert_eq!(argument, "l"),
+            _ => panic!(),
+        }
+        match configuration_error("left{l,l,l") {
+            MissingClosingCurlyBracket => {},
+            _ => panic!(),
+        }
+        match configuration_error("left{l,x,l,l}") {
+            InvalidSideAlias{expected_alias} => assert_eq!(expected_alias, "x"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn scrambled_configuration_is_valid()
+    {
+        let scramble: cube::Macro = "R U2 F' L D' B2 R' U".parse().unwrap();
+        let cube = cube::Cube::solved().sequence_moves(scramble.move_iter());
+        match try_read_arguments(&mut configuration_arguments(&cube).into_iter())
+        {
+            Ok(input) => assert!(input.initial_cube.positions_orientations() == cube.positions_orientations()),
+            Err(error) => panic!("{}", error.message()),
+        }
+    }
+
+    #[test]
+    fn scramble_replaces_configuration_arguments()
+    {
+        let notation = "R U2 F' L D' B2 R' U";
+        let scramble: cube::Macro = notation.parse().unwrap();
+        let cube = cube::Cube::solved().sequence_moves(scramble.move_iter());
+        let configured_input = try_read_arguments(&mut configuration_arguments(&cube).into_iter()).ok().unwrap();
+        let scramble_argument = format!("--scramble={}", notation);
+
+        // Without aliases, the sides are shown by their initials.
+        match try_read_arguments(&mut vec![scramble_argument.clone()].into_iter())
+        {
+            Ok(input) => {
+                assert!(input.initial_cube == configured_input.initial_cube);
+                assert_eq!(input.aliases.alias(cube::Side::F), "f");
+            },
+            Err(error) => panic!("{}", error.message()),
+        }
+        let aliases = ["1=left", "2=right", "3=down", "4=up", "5=back", "6=front"];
+        let arguments = [&[scramble_argument.as_str()][..], &aliases[..]].concat();
+        match try_read_arguments(&mut arguments.iter().map(|&argument| String::from(argument)))
+        {
+            Ok(input) => {
+                assert!(input.initial_cube == configured_input.initial_cube);
+                assert_eq!(input.aliases.alias(cube::Side::F), "6");
+            },
+            Err(error) => panic!("{}", error.message()),
+        }
+    }
+
+    #[test]
+    fn repeated_and_opposite_stickers_are_rejected()
+    {
+        let mut arguments = configuration_arguments(&cube::Cube::solved());
+        arguments[6] = String::from("left{l,l,l,d}");
+        match configuration_error(arguments.clone()) {
+            Some(SideConfigurationError::OppositeStickersOnCorner{corner, stickers}) => {
+                assert!(corner == [cube::Side::L, cube::Side::U, cube::Side::F]);
+                assert!(stickers == ["d", "u", "f"]);
+            },
+            _ => panic!(),
+        }
+        arguments[6] = String::from("left{l,l,l,f}");
+        match configuration_error(arguments) {
+            Some(SideConfigurationError::RepeatedStickerOnCorner{corner, stickers}) => {
+                assert!(corner == [cube::Side::L, cube::Side::U, cube::Side::F]);
+                assert!(stickers == ["f", "u", "f"]);
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn mirrored_corner_is_rejected()
+    {
+        // Swap the stickers on the up and front sides of the left-up-front corner.
+        let mut arguments = configuration_arguments(&cube::Cube::solved());
+        arguments[9] = String::from("up{u,u,f,u}");
+        arguments[11] = String::from("front{f,f,u,f}");
+        match configuration_error(arguments) {
+            Some(SideConfigurationError::MirroredCorner{corner, stickers}) => {
+                assert!(corner == [cube::Side::L, cube::Side::U, cube::Side::F]);
+                assert!(stickers == ["l", "f", "u"]);
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn duplicated_corner_is_rejected()
+    {
+        // Put a copy of the left-down-back cubie in the right-down-back position.
+        let mut cube = cube::Cube::solved();
+        cube.transforms[1][0][0] = cube::Transform {entries: [[0, 0, -1], [0, 1, 0], [1, 0, 0]]};
+        match configuration_error(configuration_arguments(&cube)) {
+            Some(SideConfigurationError::DuplicatedCorner{corner, other_corner, stickers}) => {
+                assert!(corner == [cube::Side::R, cube::Side::D, cube::Side::B]);
+                assert!(other_corner == [cube::Side::L, cube::Side::D, cube::Side::B]);
+                assert!(stickers == ["b", "d", "l"]);
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn twisted_corner_is_rejected()
+    {
+        // Twist the right-up-front cubie in place, one way and then the other.
+        let twists = [
+            ([[0, 0, 1], [1, 0, 0], [0, 1, 0]], 2),
+            ([[0, 1, 0], [0, 0, 1], [1, 0, 0]], 1),
+        ];
+        for &(entries, expected_twist) in twists.iter() {
+            let mut cube = cube::Cube::solved();
+            cube.transforms[1][1][1] = cube::Transform {entries};
+            match configuration_error(configuration_arguments(&cube)) {
+                Some(SideConfigurationError::TwistedCorners{twist}) => assert_eq!(twist, expected_twist),
+                _ => panic!(),
+            }
+        }
+    }
+}
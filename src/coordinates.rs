@@ -0,0 +1,171 @@
+use std;
+use cube;
+use cube::Move;
+use permutations;
+use solver::positions;
+use solver::orientations;
+
+// Coordinates use the same representation as the macro solver: positions are indexed by cubie, and
+// orientations are indexed by position.
+
+/// The number of permutations of the eight cubies.
+pub const PERMUTATION_COUNT: usize = 40320;
+/// The number of permutations that leave cubie 0 at position 0. These have the lowest ranks.
+pub const FIXED_CORNER_PERMUTATION_COUNT: usize = 5040;
+/// The number of orientations whose twists add up to a multiple of a whole turn.
+pub const ORIENTATION_COUNT: usize = 2187;
+
+const FACTORIALS: [u16; 8] = [1, 1, 2, 6, 24, 120, 720, 5040];
+
+/// Returns the rank (0 to 40319) of 'positions' in lexicographic order, so that the identity has rank 0.
+pub fn permutation_rank(positions: &[u8; 8]) -> u16 {
+    let mut rank = 0;
+    for i in 0..8 {
+        let smaller_later_count = positions[i + 1..].iter().filter(|&&p| p < positions[i]).count() as u16;
+        rank += smaller_later_count * FACTORIALS[7 - i];
+    }
+    rank
+}
+
+/// Returns the positions with the given rank, the inverse of 'permutation_rank'.
+pub fn permutation_from_rank(rank: u16) -> [u8; 8] {
+    assert!((rank as usize) < PERMUTATION_COUNT);
+    let mut remaining: Vec<u8> = (0..8).collect();
+    let mut positions = [0; 8];
+    let mut rest = rank;
+    for i in 0..8 {
+        let idx = rest / FACTORIALS[7 - i];
+        rest %= FACTORIALS[7 - i];
+        positions[i] = remaining.remove(idx as usize);
+    }
+    positions
+}
+
+/// Returns the orientations of positions 0 to 6 as a number from 0 to 2186 in base 3, with position 0 as
+/// the most significant digit. The orientation of position 7 follows from the others.
+pub fn orientation_coordinate(orientations: &[u8; 8]) -> u16 {
+    orientations[0..7].iter().fold(0, |coordinate, &o| 3*coordinate + o as u16)
+}
+
+/// Returns the orientations with the given coordinate, the inverse of 'orientation_coordinate'.
+pub fn orientations_from_coordinate(coordinate: u16) -> [u8; 8] {
+    assert!((coordinate as usize) < ORIENTATION_COUNT);
+    let mut orientations = [0; 8];
+    let mut rest = coordinate;
+    for i in (0..7).rev() {
+        orientations[i] = (rest % 3) as u8;
+        rest /= 3;
+    }
+    // The twists of all corners must add up to a multiple of a whole turn.
+    let twist = orientations.iter().fold(0, |sum, o| (sum + o) % 3);
+    orientations[7] = (3 - twist) % 3;
+    orientations
+}
+
+/// A cube state as a permutation rank and an orientation coordinate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Coordinates {
+    pub permutation: u16,
+    pub orientation: u16,
+}
+
+impl Coordinates {
+    pub fn from_cube(cube: &cube::Cube) -> Coordinates {
+        let (positions, orientations) = cube.positions_orientations();
+        Coordinates {
+            permutation: permutation_rank(&permutations::inverse(positions)),
+            orientation: orientation_coordinate(&orientations),
+        }
+    }
+
+    pub fn to_cube(&self) -> cube::Cube {
+        cube::Cube::from_positions_orientations(
+            permutations::inverse(permutation_from_rank(self.permutation)),
+            orientations_from_coordinate(self.orientation)
+        ).unwrap()
+    }
+
+    /// Returns the coordinates after applying 'm', looked up in 'tables'.
+    pub fn apply(&self, m: Move, tables: &MoveTables) -> Coordinates {
+        Coordinates {
+            permutation: tables.permutation[self.permutation as usize][m as usize],
+            orientation: tables.orientation[self.orientation as usize][m as usize],
+        }
+    }
+}
+
+/// The coordinates that each of the 18 moves leads to, from every coordinate.
+pub struct MoveTables {
+    pub permutation: Vec<[u16; 18]>,
+    pub orientation: Vec<[u16; 18]>,
+}
+
+impl MoveTables {
+    fn generate() -> MoveTables {
+        let mut permutation = vec![[0; 18]; PERMUTATION_COUNT];
+        for (rank, entries) in permutation.iter_mut().enumerate() {
+            let positions = permutation_from_rank(rank as u16);
+            for move_idx in 0..18u8 {
+                let mut p = positions;
+                positions::apply(&mut p, &Move::from(move_idx));
+                entries[move_idx as usize] = permutation_rank(&p);
+            }
+        }
+
+        // Twisting depends on the position, but not on the cubie there, so any permutation will do.
+        let mut orientation = vec![[0; 18]; ORIENTATION_COUNT];
+        for (coordinate, entries) in orientation.iter_mut().enumerate() {
+            let os = orientations_from_coordinate(coordinate as u16);
+            for move_idx in 0..18u8 {
+                let mut o = os;
+                orientations::apply(&mut o, &Move::from(move_idx));
+                entries[move_idx as usize] = orientation_coordinate(&o);
+            }
+        }
+
+        MoveTables {permutation, orientation}
+    }
+
+    /// Returns the move tables, which are generated the first time this is called.
+    pub fn get() -> &'static MoveTables {
+        static TABLES: std::sync::OnceLock<MoveTables> = std::sync::OnceLock::new();
+        TABLES.get_or_init(MoveTables::generate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_round_trip() {
+        for rank in 0..PERMUTATION_COUNT as u16 {
+            let positions = permutation_from_rank(rank);
+            assert_eq!(permutation_rank(&positions), rank);
+            assert_eq!(positions[0] == 0, (rank as usize) < FIXED_CORNER_PERMUTATION_COUNT);
+        }
+        assert_eq!(permutation_rank(&permutations::IDENTITY), 0);
+        for coordinate in 0..ORIENTATION_COUNT as u16 {
+            assert_eq!(orientation_coordinate(&orientations_from_coordinate(coordinate)), coordinate);
+        }
+    }
+
+    #[test]
+    fn tables_agree_with_cube() {
+        let tables = MoveTables::get();
+        let solved_cube = cube::Cube::solved();
+        let scramble: cube::Macro = "R U' F2 L D B' R2 U F' D2".parse().unwrap();
+        let mut cube = solved_cube.clone();
+        let mut coordinates = Coordinates::from_cube(&cube);
+        assert_eq!(coordinates, Coordinates {permutation: 0, orientation: 0});
+        for &m in scramble.move_iter() {
+            for move_idx in 0..18u8 {
+                let next = Move::from(move_idx);
+                assert_eq!(coordinates.apply(next, tables), Coordinates::from_cube(&cube.sequence(next)));
+            }
+            cube = cube.sequence(m);
+            coordinates = coordinates.apply(m, tables);
+            assert_eq!(coordinates.to_cube(), cube);
+        }
+    }
+}
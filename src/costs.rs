@@ -0,0 +1,133 @@
+use std;
+use cube;
+use cube::Move;
+
+/// The cost of each move, for choosing solutions that are quick to perform rather than short. Costs are whole
+/// numbers, such as tenths of a second.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveCosts {
+    costs: [u32; 18],
+}
+
+/// A reason why a cost table could not be read.
+#[derive(Debug)]
+pub enum CostTableError {
+    FailedToRead{error: std::io::Error},
+    // Line numbers start at 1.
+    InvalidLine{line_number: usize, line: String},
+    InvalidMove{line_number: usize, token: String},
+    InvalidCost{line_number: usize, token: String},
+    RepeatedMove{line_number: usize, m: Move},
+}
+
+impl CostTableError {
+    pub fn message(&self) -> String {
+        use self::CostTableError::*;
+        match self {
+            FailedToRead{error} =>
+                format!("failed to read the cost table: {}", error),
+            InvalidLine{line_number, line} =>
+                format!("line {}: {}: expected a move and its cost, such as \"R' 2\"", line_number, line),
+            InvalidMove{line_number, token} =>
+                format!("line {}: {}: expected a single move, such as R, U2 or F'", line_number, token),
+            InvalidCost{line_number, token} =>
+                format!("line {}: {}: expected a positive whole number", line_number, token),
+            RepeatedMove{line_number, m} =>
+                format!("line {}: the cost of {} is given more than once", line_number, m),
+        }
+    }
+}
+
+impl MoveCosts {
+    /// Every move costs 1, so that the cheapest solutions are the shortest ones in the half-turn metric.
+    pub fn uniform() -> MoveCosts {
+        MoveCosts {costs: [1; 18]}
+    }
+
+    pub fn cost(&self, m: Move) -> u32 {
+        self.costs[m as usize]
+    }
+
+    pub fn total_cost(&self, moves: &[Move]) -> u32 {
+        moves.iter().map(|&m| self.cost(m)).sum()
+    }
+
+    pub fn min_cost(&self) -> u32 {
+        *self.costs.iter().min().unwrap()
+    }
+
+    /// Reads costs from lines such as "R' 2", each with a move and its cost. Everything after a '#' is a
+    /// comment, and moves that are not listed cost 1.
+    pub fn parse(text: &str) -> Result<MoveCosts, CostTableError> {
+        use self::CostTableError::*;
+        let mut costs = MoveCosts::uniform();
+        let mut listed = [false; 18];
+        for (line_idx, full_line) in text.lines().enumerate() {
+            let line_number = line_idx + 1;
+            let line = full_line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() != 2 {
+                return Err(InvalidLine{line_number, line: String::from(line)});
+            }
+            // Rotations and sequences of moves also parse as macros, but only a single move has a cost.
+            let m = {
+                let parsed_tokens = tokens[0].parse::<cube::Macro>().map(|parsed_macro| parsed_macro.tokens());
+                match parsed_tokens.as_deref() {
+                    Ok(&[cube::Token::Move(m)]) => m,
+                    _ => return Err(InvalidMove{line_number, token: String::from(tokens[0])}),
+                }
+            };
+            let cost = {
+                match tokens[1].parse::<u32>() {
+                    Ok(cost) if cost > 0 => cost,
+                    _ => return Err(InvalidCost{line_number, token: String::from(tokens[1])}),
+                }
+            };
+            if listed[m as usize] {
+                return Err(RepeatedMove{line_number, m});
+            }
+            listed[m as usize] = true;
+            costs.costs[m as usize] = cost;
+        }
+        Ok(costs)
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<MoveCosts, CostTableError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => MoveCosts::parse(&text),
+            Err(error) => Err(CostTableError::FailedToRead{error}),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_costs() {
+        let costs = MoveCosts::parse("# Fingertricks\nR 1\nU' 2  # regrip\n\nB2 5\n").unwrap();
+        let moves = "R U' B2 F".parse::<cube::Macro>().unwrap().moves;
+        assert_eq!(moves.iter().map(|&m| costs.cost(m)).collect::<Vec<u32>>(), vec![1, 2, 5, 1]);
+        assert_eq!(costs.total_cost(&moves), 9);
+        assert_eq!(costs.min_cost(), 1);
+
+        let line_error = |text: &str| {
+            match MoveCosts::parse(text) {
+                Err(CostTableError::InvalidLine{line_number, ..}) |
+                Err(CostTableError::InvalidMove{line_number, ..}) |
+                Err(CostTableError::InvalidCost{line_number, ..}) |
+                Err(CostTableError::RepeatedMove{line_number, ..}) => line_number,
+                _ => panic!(),
+            }
+        };
+        assert_eq!(line_error("R 1\nU"), 2);
+        assert_eq!(line_error("R U 1"), 1);
+        assert_eq!(line_error("x 1"), 1);
+        assert_eq!(line_error("R 0"), 1);
+        assert_eq!(line_error("R 1\nR' 1\n# again\nR 2"), 4);
+    }
+}
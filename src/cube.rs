@@ -1,11 +1,20 @@
 use std;
 
-#[derive(Debug, Clone)]
+/// The state of a cube.
+///
+/// Each of the eight corner positions is indexed by its (x, y, z) coordinates, where 0 stands for the
+/// negative direction (left, down, back) and 1 for the positive direction (right, up, front). The transform
+/// at a position rotates the corner cubie that belongs there in the solved cube into its current place.
+///
+/// The transforms determine the physical state, so cubes compare equal exactly when they look the same from
+/// every side. The ordering has no meaning beyond being consistent, for sorting and searching.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Cube {
     pub transforms: [[[Transform; 2]; 2]; 2]
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A rotation of 3-space that maps the cube onto itself, as an orthogonal integer matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Transform {
     pub entries: [[i32; 3]; 3],
 }
@@ -20,6 +29,13 @@ impl Transform {
         Transform {entries: entries}
     }
 
+    pub fn determinant(&self) -> i32 {
+        let e = &self.entries;
+        e[0][0]*(e[1][1]*e[2][2] - e[1][2]*e[2][1]) -
+            e[0][1]*(e[1][0]*e[2][2] - e[1][2]*e[2][0]) +
+            e[0][2]*(e[1][0]*e[2][1] - e[1][1]*e[2][0])
+    }
+
     pub fn inverse(&self) -> Transform {
         // We know that the transform is orthogonal, so the inverse is just the transpose.
         let mut transposed_entries = [[0; 3]; 3];
@@ -30,6 +46,7 @@ impl Transform {
         }
         Transform{entries: transposed_entries}
     }
+
     fn sequence(&self, next: Transform) -> Transform {
         let mut e = [[0; 3]; 3];
         for i in 0..3 {
@@ -87,48 +104,180 @@ fn vector_direction_index(vector: &[i32; 3]) -> usize{
     panic!();
 }
 
+// Returns the position where a cubie with 'transform' at 'position_idx' belongs in the solved cube, and its
+// orientation.
+fn solved_position_orientation(position_idx: usize, transform: &Transform) -> (u8, u8)
+{
+    let cubicle_index = [
+        ((position_idx >> 0) & 1) as i32,
+        ((position_idx >> 1) & 1) as i32,
+        ((position_idx >> 2) & 1) as i32,
+    ];
+    let cubicle_position = index_position(&cubicle_index);
+    let cubicle_face_normals = cubicle_face_normals(&cubicle_position);
+    let inverse_transform = transform.inverse();
+    let solved_normals = [
+        inverse_transform.apply(&cubicle_face_normals[0]),
+        inverse_transform.apply(&cubicle_face_normals[1]),
+        inverse_transform.apply(&cubicle_face_normals[2]),
+    ];
+    let solved_position = [
+        solved_normals[0][0] + solved_normals[1][0] + solved_normals[2][0],
+        solved_normals[0][1] + solved_normals[1][1] + solved_normals[2][1],
+        solved_normals[0][2] + solved_normals[1][2] + solved_normals[2][2],
+    ];
+    let solved_position_index = position_index(&solved_position);
+    let solved_position_idx =
+        (solved_position_index[0] << 0) | (solved_position_index[1] << 1) | (solved_position_index[2] << 2);
+    let orientation =
+        [[0, 2, 1], [0, 1, 2]]
+        [(solved_position_idx.count_ones() & 1) as usize]
+        [vector_direction_index(&solved_normals[0])];
+    return (solved_position_idx as u8, orientation);
+}
+
+// Returns the 24 rotations that map the cube onto itself.
+fn rotation_transforms() -> Vec<Transform>
+{
+    let mut transforms = vec![Transform::identity()];
+    let mut idx = 0;
+    while idx < transforms.len() {
+        for &r in [Rotation::X3, Rotation::Y3].iter() {
+            let transform = transforms[idx].sequence(r.transform());
+            if !transforms.contains(&transform) {
+                transforms.push(transform);
+            }
+        }
+        idx += 1;
+    }
+    assert!(transforms.len() == 24);
+    return transforms;
+}
+
 impl Cube {
 
+    /// Returns the solved cube, with every cubie home in its solved orientation.
+    pub fn solved() -> Cube {
+        Cube {transforms: [[[Transform::identity(); 2]; 2]; 2]}
+    }
+
+    /// Returns whether the cube is solved, in any whole-cube rotation: that is, whether every cubie has been
+    /// rotated the same way.
+    pub fn is_solved(&self) -> bool {
+        let first_transform = self.transforms[0][0][0];
+        self.transforms.iter().flatten().flatten().all(|&transform| transform == first_transform)
+    }
+
+    /// Builds a cube from the stickers on each of its sides.
+    ///
+    /// 'configuration[side as usize]' lists the sides where the four stickers on 'side' belong in the solved
+    /// cube. Bit 0 of a sticker index selects the position along the first of the two remaining axes (in
+    /// x, y, z order), and bit 1 along the second, with 0 being the negative direction.
+    pub fn from_stickers(configuration: &[[Side; 4]; 6]) -> Cube {
+        let mut transforms = [[[Transform::identity(); 2]; 2]; 2];
+        for side_idx in 0..6 {
+            for entry_idx in 0..4 {
+                let d0 = side_idx/2;
+                let d1 = [1, 0, 0][d0];
+                let d2 = [2, 2, 1][d0];
+                let i0 = side_idx & 1;
+                let (i1, i2) = ((entry_idx >> 0) & 1, (entry_idx >> 1) & 1);
+                let mut index = [0; 3];
+                index[d0] = i0;
+                index[d1] = i1;
+                index[d2] = i2;
+                let side = Side::from(side_idx as i32);
+                let solved_side = configuration[side_idx][entry_idx];
+                let side_normal = normal(side);
+                let solved_side_normal = normal(solved_side);
+                let column_idx = {
+                    let mut idx = 0;
+                    while idx < 3 {
+                        if solved_side_normal[idx] != 0 {
+                            break;
+                        }
+                        assert!(solved_side_normal[idx] == 0);
+                        idx += 1;
+                    }
+                    assert!(idx < 3);
+                    idx
+                };
+                for row_idx in 0..3 {
+                    transforms[index[0]][index[1]][index[2]].entries[row_idx][column_idx] =
+                        solved_side_normal[column_idx]*side_normal[row_idx];
+                }
+            }
+        }
+
+        Cube {transforms: transforms}
+    }
+
+    /// Returns, for each position, the position where the cubie currently there belongs in the solved cube,
+    /// and, for each position, the orientation (0, 1 or 2) of the cubie currently there.
     pub fn positions_orientations(&self) -> ([u8; 8], [u8; 8])
     {
         let mut ps = [0u8; 8];
         let mut os = [0u8; 8];        
         for position_idx in 0..8 {
             let cubicle_index = [(position_idx >> 0) & 1, (position_idx >> 1) & 1, (position_idx >> 2) & 1];
-            let cubicle_position = index_position(&cubicle_index);
-            let cubicle_face_normals = cubicle_face_normals(&cubicle_position);
-            let transform = &self.transforms[cubicle_index[0] as usize]
-                [cubicle_index[1] as usize][cubicle_index[2] as usize];
-            let inverse_transform = transform.inverse();
-            let solved_normals = [
-                inverse_transform.apply(&cubicle_face_normals[0]),
-                inverse_transform.apply(&cubicle_face_normals[1]),
-                inverse_transform.apply(&cubicle_face_normals[2]),
-            ];
-            let solved_position = [
-                solved_normals[0][0] + solved_normals[1][0] + solved_normals[2][0],
-                solved_normals[0][1] + solved_normals[1][1] + solved_normals[2][1],
-                solved_normals[0][2] + solved_normals[1][2] + solved_normals[2][2],
-            ];
-            let solved_position_index = position_index(&solved_position);
-            let solved_position_idx =
-                (solved_position_index[0] << 0) | (solved_position_index[1] << 1) | (solved_position_index[2] << 2);
-            ps[position_idx as usize] = solved_position_idx as u8;
-            os[position_idx as usize] =
-                [[0, 2, 1], [0, 1, 2]]
-                [(solved_position_idx.count_ones() & 1) as usize]
-                [vector_direction_index(&solved_normals[0])];
+            let transform = &self.transforms[cubicle_index[0]][cubicle_index[1]][cubicle_index[2]];
+            let (solved_position_idx, orientation) = solved_position_orientation(position_idx, transform);
+            ps[position_idx] = solved_position_idx;
+            os[position_idx] = orientation;
         }
         return (ps, os);
     }
+
+    /// Builds a cube from positions and orientations as returned by 'positions_orientations'. Fails unless
+    /// 'positions' is a permutation, and the orientations are each 0, 1 or 2 and add up to a multiple of 3.
+    pub fn from_positions_orientations(positions: [u8; 8], orientations: [u8; 8]) -> Result<Cube, StateError> {
+        use self::StateError::*;
+        for position_idx in 0..8 {
+            let position = positions[position_idx];
+            if position >= 8 {
+                return Err(PositionOutOfRange{position_idx, position});
+            }
+            if positions[0..position_idx].contains(&position) {
+                return Err(RepeatedPosition{position});
+            }
+            let orientation = orientations[position_idx];
+            if orientation >= 3 {
+                return Err(OrientationOutOfRange{position_idx, orientation});
+            }
+        }
+        let twist = orientations.iter().fold(0, |sum, o| (sum + o) % 3);
+        if twist != 0 {
+            return Err(TwistedCorners{twist});
+        }
+
+        let rotation_transforms = rotation_transforms();
+        let mut transforms = [[[Transform::identity(); 2]; 2]; 2];
+        for position_idx in 0..8 {
+            let &transform = rotation_transforms.iter().find(|transform| {
+                solved_position_orientation(position_idx, transform) ==
+                    (positions[position_idx], orientations[position_idx])
+            }).unwrap();
+            transforms[(position_idx >> 0) & 1][(position_idx >> 1) & 1][(position_idx >> 2) & 1] = transform;
+        }
+        Ok(Cube {transforms: transforms})
+    }
+
     
+    /// Returns the transform of the cubie at 'position', whose coordinates are each -1 or 1.
     pub fn transform(&self, position: [i32; 3]) -> &Transform {
         assert!(in_cube(&position));
         let (i, j, k)=
             ((1 + position[0]) >> 1, (1 + position[1]) >> 1, (1 + position[2]) >> 1);
         &self.transforms[i as usize][j as usize][k as usize]
     }
-    fn sequence(&self, m: Move) -> Cube {
+    /// Returns the side where the sticker on 'side' at the corner 'position' belongs in the solved cube, which
+    /// is its colour. The coordinates of 'position' are each -1 or 1, and the corner must be on 'side'.
+    pub fn sticker(&self, position: [i32; 3], side: Side) -> Side {
+        assert!(inner_product(&position, &normal(side)) == 1);
+        normal_side(self.transform(position).inverse().apply(&normal(side)))
+    }
+    /// Returns the cube that results from applying 'm' to this cube.
+    pub fn sequence(&self, m: Move) -> Cube {
         let mut transformed_cube = (*self).clone();
         let move_transform = m.transform();
         let side = m.side();
@@ -158,6 +307,7 @@ impl Cube {
         return transformed_cube;
     }
 
+    /// Returns the cube that results from applying 'moves' in order to this cube.
     pub fn sequence_moves(&self, moves: std::slice::Iter<Move>) -> Cube {
         let mut transformed_cube = (*self).clone();
         for m in moves {
@@ -165,8 +315,105 @@ impl Cube {
         }
         return transformed_cube;
     }
+
+    /// Returns the cube that results from rotating this whole cube by 'r'.
+    pub fn rotate(&self, r: Rotation) -> Cube {
+        let mut rotated_cube = (*self).clone();
+        let rotation_transform = r.transform();
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    let start_position = index_position(&[i, j, k]);
+                    let &start_transform = self.transform(start_position);
+                    let end_index = position_index(&rotation_transform.apply(&start_position));
+                    rotated_cube.transforms[end_index[0] as usize][end_index[1] as usize][end_index[2] as usize] =
+                        start_transform.sequence(rotation_transform);
+                }
+            }
+        }
+        return rotated_cube;
+    }
+
+    /// Returns the cube that results from applying 'rotations' in order to this cube.
+    pub fn rotate_all(&self, rotations: std::slice::Iter<Rotation>) -> Cube {
+        let mut rotated_cube = (*self).clone();
+        for r in rotations {
+            rotated_cube = rotated_cube.rotate(*r);
+        }
+        return rotated_cube;
+    }
+
+    /// Returns at most two rotations that, applied in order, bring the cubie that belongs at
+    /// 'reference_position_idx' home in its solved orientation. Position indices are x | y << 1 | z << 2.
+    pub fn normalizing_rotations(&self, reference_position_idx: u8) -> Vec<Rotation> {
+        let (positions, _) = self.positions_orientations();
+        let current_position_idx = positions.iter().position(|&p| p == reference_position_idx).unwrap();
+        let current_index = [
+            ((current_position_idx >> 0) & 1) as i32,
+            ((current_position_idx >> 1) & 1) as i32,
+            ((current_position_idx >> 2) & 1) as i32,
+        ];
+        // Undo the transform of the reference cubie.
+        let goal_transform = self.transform(index_position(&current_index)).inverse();
+
+        let mut candidates: Vec<Vec<Rotation>> = vec![vec![]];
+        for idx in 0..9u8 {
+            candidates.push(vec![Rotation::from(idx)]);
+        }
+        for first_idx in 0..9u8 {
+            for second_idx in 0..9u8 {
+                candidates.push(vec![Rotation::from(first_idx), Rotation::from(second_idx)]);
+            }
+        }
+        for rotations in candidates {
+            let transform =
+                rotations.iter().fold(Transform::identity(), |transform, r| transform.sequence(r.transform()));
+            if transform == goal_transform {
+                return rotations;
+            }
+        }
+        // Every rotation of the cube is a product of at most two rotations about the x, y and z axes.
+        panic!();
+    }
+
+    /// Returns this cube, rotated so that the cubie that belongs at 'reference_position_idx' is home in its
+    /// solved orientation.
+    pub fn normalized(&self, reference_position_idx: u8) -> Cube {
+        self.rotate_all(self.normalizing_rotations(reference_position_idx).iter())
+    }
+}
+
+/// A reason why positions and orientations do not describe a cube.
+#[derive(Debug, PartialEq)]
+pub enum StateError {
+    /// The cubie at 'position_idx' belongs at 'position', which is not one of the positions 0 to 7.
+    PositionOutOfRange{position_idx: usize, position: u8},
+    /// Two cubies belong at 'position'.
+    RepeatedPosition{position: u8},
+    /// The cubie at 'position_idx' has 'orientation', which is not one of the orientations 0, 1 and 2.
+    OrientationOutOfRange{position_idx: usize, orientation: u8},
+    /// The orientations add up to 'twist' more than a multiple of 3, which no sequence of moves can lead to.
+    TwistedCorners{twist: u8},
+}
+
+impl StateError {
+    pub fn message(&self) -> String {
+        use self::StateError::*;
+        match self {
+            &PositionOutOfRange{position_idx, position} =>
+                format!("the cubie at position {} belongs at {}, which is not a position", position_idx, position),
+            &RepeatedPosition{position} =>
+                format!("more than one cubie belongs at position {}", position),
+            &OrientationOutOfRange{position_idx, orientation} =>
+                format!("the cubie at position {} has orientation {}, which is not an orientation",
+                        position_idx, orientation),
+            &TwistedCorners{twist} =>
+                format!("the orientations add up to {} more than a multiple of 3", twist),
+        }
+    }
 }
 
+/// A side of the cube, named from the point of view of someone facing the front side.
 #[derive(Debug)]
 #[derive(Copy)]
 #[derive(Clone)]
@@ -190,6 +437,10 @@ impl Side {
             _ => panic!(),
         }
     }
+    /// Returns the side written as 'letter' in move notation, such as 'R' for the right side.
+    pub fn from_letter(letter: char) -> Option<Side> {
+        side_letter_idx(letter).map(|idx| Side::from(idx as i32))
+    }
     pub fn deserialize(serialization: &str) -> Option<Side> {
         use self::Side::*;
         match serialization {
@@ -216,7 +467,9 @@ impl Side {
 }
 
 
-#[derive(Clone, Copy, Debug)]
+/// A turn of one side: 1 is a quarter turn counter-clockwise (looking at the side), 2 is a half turn and 3 is
+/// a quarter turn clockwise. In standard notation, 'R1' is therefore written "R'" and 'R3' is written "R".
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Move {
     L1, L2, L3,
     R1, R2, R3,
@@ -230,6 +483,18 @@ impl Move {
     fn side(&self) -> Side {
         Side::from( ((*self) as i32) / 3 )
     }
+    /// Returns the move that undoes this move.
+    pub fn inverse(&self) -> Move {
+        let idx = (*self) as u8;
+        Move::from(3*(idx/3) + 2 - idx%3)
+    }
+    /// Returns the move that has the same effect after rotating the whole cube by 'r' as this move has before,
+    /// that is, the same turn of the side that 'r' takes this move's side to.
+    pub fn rotated(&self, r: Rotation) -> Move {
+        let idx = (*self) as u8;
+        let side = normal_side(r.transform().apply(&normal(self.side())));
+        Move::from(3*(side as u8) + idx%3)
+    }
     fn transform(&self) -> Transform {
         use self::Move::*;
         let e = 
@@ -294,9 +559,60 @@ impl Move {
 }
 
 
-#[derive(Debug)]
+/// A rotation of the whole cube, about the axis through the right, up or front side. As for moves, 1 is a
+/// quarter turn counter-clockwise (looking at that side), 2 is a half turn and 3 is a quarter turn clockwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rotation {
+    X1, X2, X3,
+    Y1, Y2, Y3,
+    Z1, Z2, Z3,
+}
+
+impl Rotation {
+    /// Returns the rotation that undoes this rotation.
+    pub fn inverse(&self) -> Rotation {
+        let idx = (*self) as u8;
+        Rotation::from(3*(idx/3) + 2 - idx%3)
+    }
+    /// Returns two moves of opposite sides that together turn the whole cube like this rotation does.
+    pub fn moves(&self) -> [Move; 2] {
+        let idx = (*self) as u8;
+        let (axis_idx, turn) = (idx/3, idx%3);
+        [Move::from(3*(2*axis_idx) + 2 - turn), Move::from(3*(2*axis_idx + 1) + turn)]
+    }
+    fn transform(&self) -> Transform {
+        // The same as turning the side that the axis goes through.
+        let idx = (*self) as u8;
+        Move::from(3*(2*(idx/3) + 1) + idx%3).transform()
+    }
+}
+
+impl From<u8> for Rotation {
+    fn from(i: u8) -> Rotation {
+        use self::Rotation::*;
+        match i {
+            0 => X1, 1 => X2, 2 => X3,
+            3 => Y1, 4 => Y2, 5 => Y3,
+            6 => Z1, 7 => Z2, 8 => Z3,
+            _ => panic!(),
+        }
+    }
+}
+
+/// A move or a whole-cube rotation, as written in a sequence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Move(Move),
+    Rotation(Rotation),
+}
+
+/// A sequence of moves. A whole-cube rotation in it is applied as the two turns of opposite sides that it amounts
+/// to on a 2x2 cube, but it is still written as a rotation.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Macro {
     pub moves: Vec<Move>,
+    // The rotations, each with the index in 'moves' of the first of its two turns.
+    rotations: Vec<(usize, Rotation)>,
 }
 
 impl Macro {
@@ -305,44 +621,127 @@ impl Macro {
     }
     pub fn identity() -> Macro {
         Macro {
-            moves: vec![]
+            moves: vec![],
+            rotations: vec![],
         }
     }
+
+    /// Returns the sequence as it is written, with rotations instead of their turns.
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut idx = 0;
+        while idx < self.moves.len() {
+            let optional_rotation = self.rotations.iter()
+                .find(|&&(rotation_idx, r)| rotation_idx == idx && self.moves[idx..].starts_with(&r.moves()))
+                .map(|&(_, r)| r);
+            match optional_rotation {
+                Some(r) => {
+                    tokens.push(Token::Rotation(r));
+                    idx += 2;
+                },
+                None => {
+                    tokens.push(Token::Move(self.moves[idx]));
+                    idx += 1;
+                },
+            }
+        }
+        tokens
+    }
+
+    /// Returns the moves that are not part of a rotation, which are the ones that count towards its length.
+    pub fn face_moves(&self) -> Vec<Move> {
+        self.tokens().into_iter().filter_map(|token| match token {
+            Token::Move(m) => Some(m),
+            Token::Rotation(_) => None,
+        }).collect()
+    }
+
+    /// Returns an equivalent sequence in canonical form. Turns of the same side are merged, or cancelled if
+    /// they add up to a whole turn, also across turns of the opposite side since those commute with them. Turns
+    /// of opposite sides are ordered with the left, down and back sides first. Rotations are simplified as their
+    /// turns, so they are no longer written as rotations.
+    pub fn simplified(&self) -> Macro {
+        let mut moves: Vec<Move> = Vec::new();
+        for &m in self.moves.iter() {
+            let side_idx = (m as u8) / 3;
+            let axis_idx = side_idx / 2;
+            // The moves at the end that turn sides on the same axis as 'm', at most one per side.
+            let mut axis_moves_idx = moves.len();
+            while axis_moves_idx > 0 && (moves[axis_moves_idx - 1] as u8) / 6 == axis_idx {
+                axis_moves_idx -= 1;
+            }
+            let optional_same_side_idx =
+                (axis_moves_idx..moves.len()).find(|&idx| (moves[idx] as u8) / 3 == side_idx);
+            match optional_same_side_idx {
+                Some(idx) => {
+                    let quarter_turns = ((moves[idx] as u8) % 3 + (m as u8) % 3 + 2) % 4;
+                    if quarter_turns == 0 {
+                        moves.remove(idx);
+                    } else {
+                        moves[idx] = Move::from(3*side_idx + quarter_turns - 1);
+                    }
+                },
+                None => {
+                    let insert_idx =
+                        (axis_moves_idx..moves.len()).find(|&idx| (moves[idx] as u8) / 3 > side_idx)
+                        .unwrap_or(moves.len());
+                    moves.insert(insert_idx, m);
+                },
+            }
+        }
+        Macro::from(moves)
+    }
 }
 
 impl From<Vec<Move>> for Macro {
     fn from(v: Vec<Move>) -> Macro {
-        Macro {moves: v}
+        Macro {moves: v, rotations: vec![]}
     }
 }
 
 impl From<[Move; 4]> for Macro {
     fn from(v: [Move; 4]) -> Macro {
-        Macro {moves: v.to_vec()}
+        Macro {moves: v.to_vec(), rotations: vec![]}
     }
 }
 
 impl From<[Move; 13]> for Macro {
     fn from(v: [Move; 13]) -> Macro {
-        Macro {moves: v.to_vec()}
+        Macro {moves: v.to_vec(), rotations: vec![]}
     }
 }
 
 impl From<[Move; 11]> for Macro {
     fn from(v: [Move; 11]) -> Macro {
-        Macro {moves: v.to_vec()}
+        Macro {moves: v.to_vec(), rotations: vec![]}
     }
 }
 
-impl<'a> From<&'a [Move]> for Macro {
+impl From<&[Move]> for Macro {
     fn from(s: &[Move]) -> Macro {
-        Macro {moves: s.to_vec()}
+        Macro {moves: s.to_vec(), rotations: vec![]}
     }
 }
 
 impl From<Move> for Macro {
     fn from(m: Move) -> Macro {
-        Macro {moves: vec![m]}
+        Macro {moves: vec![m], rotations: vec![]}
+    }
+}
+
+impl From<Vec<Token>> for Macro {
+    fn from(tokens: Vec<Token>) -> Macro {
+        let mut m = Macro::identity();
+        for token in tokens {
+            match token {
+                Token::Move(move_) => m.moves.push(move_),
+                Token::Rotation(r) => {
+                    m.rotations.push((m.moves.len(), r));
+                    m.moves.extend_from_slice(&r.moves());
+                },
+            }
+        }
+        m
     }
 }
 
@@ -375,6 +774,164 @@ impl From<i32> for Move {
     }
 }
 
+/// An error in a move sequence written in standard notation.
+#[derive(Debug, PartialEq)]
+pub enum NotationError {
+    /// The token starting at 'column' (counting characters from 1) is not a move.
+    InvalidMove{column: usize, token: String},
+    /// A single move was expected, but 'move_count' moves were given.
+    ExpectedSingleMove{move_count: usize},
+}
+
+impl NotationError {
+    pub fn message(&self) -> String {
+        use self::NotationError::*;
+        match self {
+            &InvalidMove{column, ref token} =>
+                format!("column {}: invalid move: '{}'", column, token),
+            &ExpectedSingleMove{move_count} =>
+                format!("expected a single move, but got {}", move_count),
+        }
+    }
+}
+
+/// Moves are written in standard notation: the side letter alone is a clockwise quarter turn (3 here), a
+/// trailing "'" makes it counter-clockwise (1 here), and a trailing "2" makes it a half turn.
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let side_letter = ["L", "R", "D", "U", "B", "F"][(*self as usize) / 3];
+        let suffix = ["'", "2", ""][(*self as usize) % 3];
+        write!(f, "{}{}", side_letter, suffix)
+    }
+}
+
+/// Rotations are written "x", "y" and "z", with the same suffixes as moves.
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let axis_letter = ["x", "y", "z"][(*self as usize) / 3];
+        let suffix = ["'", "2", ""][(*self as usize) % 3];
+        write!(f, "{}{}", axis_letter, suffix)
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            &Token::Move(m) => write!(f, "{}", m),
+            &Token::Rotation(r) => write!(f, "{}", r),
+        }
+    }
+}
+
+impl std::fmt::Display for Macro {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (idx, token) in self.tokens().iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_prime(c: char) -> bool {
+    c == '\'' || c == '\u{2019}' || c == '\u{2032}'
+}
+
+fn side_letter_idx(c: char) -> Option<u8> {
+    "LRDUBF".chars().position(|l| l == c).map(|idx| idx as u8)
+}
+
+fn axis_letter_idx(c: char) -> Option<u8> {
+    "xyz".chars().position(|l| l == c).map(|idx| idx as u8)
+}
+
+/// Parses moves in standard notation, such as "R U2 F' R2' U\u{2019}". Whitespace between moves is optional.
+/// A whole-cube rotation, such as "x'", is applied as the two turns of opposite sides that it amounts to on a 2x2
+/// cube, and written back as a rotation.
+impl std::str::FromStr for Macro {
+    type Err = NotationError;
+    fn from_str(s: &str) -> Result<Macro, NotationError> {
+        let characters: Vec<char> = s.chars().collect();
+        let mut tokens = Vec::new();
+        let mut idx = 0;
+        while idx < characters.len() {
+            if characters[idx].is_whitespace() {
+                idx += 1;
+                continue;
+            }
+            let token_idx = idx;
+            let optional_side_idx = side_letter_idx(characters[idx]);
+            let optional_axis_idx = axis_letter_idx(characters[idx]);
+            idx += 1;
+            // 0 is counter-clockwise, 1 is a half turn, and 2 is clockwise.
+            let mut turn = 2;
+            if idx < characters.len() && characters[idx] == '2' {
+                turn = 1;
+                idx += 1;
+                // A half turn is the same in both directions.
+                if idx < characters.len() && is_prime(characters[idx]) {
+                    idx += 1;
+                }
+            } else if idx < characters.len() && is_prime(characters[idx]) {
+                turn = 0;
+                idx += 1;
+            }
+            let token_ends =
+                idx == characters.len() || characters[idx].is_whitespace() ||
+                side_letter_idx(characters[idx]).is_some() || axis_letter_idx(characters[idx]).is_some();
+            match (optional_side_idx, optional_axis_idx) {
+                (Some(side_idx), _) if token_ends => tokens.push(Token::Move(Move::from(3*side_idx + turn))),
+                (_, Some(axis_idx)) if token_ends => tokens.push(Token::Rotation(Rotation::from(3*axis_idx + turn))),
+                _ => {
+                    let token_length =
+                        characters[token_idx..].iter().position(|c| c.is_whitespace())
+                        .unwrap_or(characters.len() - token_idx);
+                    let token: String = characters[token_idx .. token_idx + token_length].iter().collect();
+                    return Err(NotationError::InvalidMove{column: token_idx + 1, token});
+                },
+            }
+        }
+        Ok(Macro::from(tokens))
+    }
+}
+
+impl std::str::FromStr for Rotation {
+    type Err = NotationError;
+    fn from_str(s: &str) -> Result<Rotation, NotationError> {
+        let trimmed = s.trim();
+        let characters: Vec<char> = trimmed.chars().collect();
+        let turn = {
+            match &characters[1.min(characters.len())..] {
+                &[] => Some(2),
+                &[c] if is_prime(c) => Some(0),
+                &['2'] => Some(1),
+                &['2', c] if is_prime(c) => Some(1),
+                _ => None,
+            }
+        };
+        match (characters.first().and_then(|&c| axis_letter_idx(c)), turn) {
+            (Some(axis_idx), Some(turn)) => Ok(Rotation::from(3*axis_idx + turn)),
+            _ => {
+                let column = s.chars().position(|c| !c.is_whitespace()).unwrap_or(0) + 1;
+                Err(NotationError::InvalidMove{column, token: String::from(trimmed)})
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for Move {
+    type Err = NotationError;
+    fn from_str(s: &str) -> Result<Move, NotationError> {
+        let m: Macro = s.parse()?;
+        if m.moves.len() != 1 {
+            return Err(NotationError::ExpectedSingleMove{move_count: m.moves.len()});
+        }
+        Ok(m.moves[0])
+    }
+}
+
 pub fn normal(side: Side) -> [i32; 3] {
     use self::Side::*;
     match side {
@@ -428,3 +985,201 @@ fn is_face_normal(normal: [i32; 3]) -> bool {
     }
     return zero_count == 2;
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation_round_trip() {
+        for idx in 0..18u8 {
+            let m = Move::from(idx);
+            assert_eq!(m.to_string().parse::<Move>(), Ok(m));
+        }
+        let m: Macro = "R U2 F' L D' B2".parse().unwrap();
+        assert_eq!(m.to_string(), "R U2 F' L D' B2");
+    }
+
+    #[test]
+    fn notation_suffixes() {
+        use self::Move::*;
+        let m: Macro = " R2'  U\u{2019}F\tL2\nD ".parse().unwrap();
+        assert_eq!(m.moves, vec![R2, U1, F3, L2, D3]);
+    }
+
+    #[test]
+    fn notation_error_columns() {
+        assert_eq!(
+            "R U2 X F".parse::<Macro>().err(),
+            Some(NotationError::InvalidMove{column: 6, token: String::from("X")})
+        );
+        assert_eq!(
+            "R\u{2019} U3 F".parse::<Macro>().err(),
+            Some(NotationError::InvalidMove{column: 4, token: String::from("U3")})
+        );
+        assert_eq!(
+            "R U".parse::<Move>().err(),
+            Some(NotationError::ExpectedSingleMove{move_count: 2})
+        );
+    }
+
+    fn simplified(notation: &str) -> String {
+        notation.parse::<Macro>().unwrap().simplified().to_string()
+    }
+
+    #[test]
+    fn simplify_same_side() {
+        assert_eq!(simplified("F F'"), "");
+        assert_eq!(simplified("U' U'"), "U2");
+        assert_eq!(simplified("R2 R"), "R'");
+        assert_eq!(simplified("R U U' R'"), "");
+        assert_eq!(simplified("F R U R' U' F'"), "F R U R' U' F'");
+    }
+
+    #[test]
+    fn simplify_across_opposite_side() {
+        assert_eq!(simplified("R L R'"), "L");
+        assert_eq!(simplified("R L2 R"), "L2 R2");
+        assert_eq!(simplified("U D U D"), "D2 U2");
+        assert_eq!(simplified("B F' B' F U"), "U");
+    }
+
+    #[test]
+    fn simplify_keeps_state() {
+        let solved = Cube::solved();
+        let mut seed = 1u32;
+        for _ in 0..20 {
+            let mut moves = Vec::new();
+            for _ in 0..40 {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                moves.push(Move::from(((seed >> 16) % 18) as u8));
+            }
+            let m = Macro::from(moves);
+            let s = m.simplified();
+            assert!(s.moves.len() < m.moves.len());
+            assert!(solved.sequence_moves(m.move_iter()).positions_orientations() ==
+                    solved.sequence_moves(s.move_iter()).positions_orientations());
+            assert_eq!(s.simplified().to_string(), s.to_string());
+        }
+    }
+
+    #[test]
+    fn rotations_are_turns_of_opposite_sides() {
+        let cube = Cube::solved().sequence_moves("R U F' R2 D B'".parse::<Macro>().unwrap().move_iter());
+        for idx in 0..9u8 {
+            let r = Rotation::from(idx);
+            assert_eq!(r.to_string().parse::<Rotation>(), Ok(r));
+            assert_eq!(cube.rotate(r), cube.sequence_moves(r.moves().iter()));
+            assert_eq!(cube.rotate(r).rotate(r.inverse()), cube);
+        }
+        let m: Macro = "x y' z2".parse().unwrap();
+        assert_eq!(m.moves, "L' R D U' B2 F2".parse::<Macro>().unwrap().moves);
+    }
+
+    #[test]
+    fn rotations_round_trip() {
+        let m: Macro = "x R y'U2 z2 F'".parse().unwrap();
+        assert_eq!(m.to_string(), "x R y' U2 z2 F'");
+        assert_eq!(m.tokens()[..2], [Token::Rotation(Rotation::X3), Token::Move(Move::R3)]);
+        assert_eq!(m.moves.len(), 9);
+        assert_eq!(m.face_moves(), vec![Move::R3, Move::U2, Move::F1]);
+        assert_eq!(m.to_string().parse::<Macro>(), Ok(m.clone()));
+        // Once simplified, the turns of a rotation may merge with other moves, so they are written as moves.
+        assert_eq!("x R".parse::<Macro>().unwrap().simplified().to_string(), "L' R2");
+    }
+
+    #[test]
+    fn rotated_moves_commute_with_rotations() {
+        let cube = Cube::solved().sequence_moves("R U F' R2 D B'".parse::<Macro>().unwrap().move_iter());
+        for r_idx in 0..9u8 {
+            let r = Rotation::from(r_idx);
+            for m_idx in 0..18u8 {
+                let m = Move::from(m_idx);
+                assert_eq!(cube.sequence(m).rotate(r), cube.rotate(r).sequence(m.rotated(r)));
+            }
+        }
+        // After "y", the right side faces front.
+        assert_eq!(Move::R3.rotated(Rotation::Y3), Move::F3);
+    }
+
+    #[test]
+    fn normalizing_brings_reference_corner_home() {
+        let scramble: Macro = "F R' U2 x D L2 y' B".parse().unwrap();
+        let cube = Cube::solved().sequence_moves(scramble.move_iter());
+        for reference_position_idx in 0..8u8 {
+            let (positions, orientations) = cube.normalized(reference_position_idx).positions_orientations();
+            assert_eq!(positions[reference_position_idx as usize], reference_position_idx);
+            assert_eq!(orientations[reference_position_idx as usize], 0);
+        }
+        // A rotated solved cube is solved once normalized.
+        let rotated_cube = Cube::solved().rotate(Rotation::X3).rotate(Rotation::Z2);
+        assert_eq!(rotated_cube.normalized(5), Cube::solved());
+    }
+
+    #[test]
+    fn equality_and_solved_states() {
+        use std::collections::HashSet;
+        assert!(Cube::solved().is_solved());
+        // "R L'" turns the whole cube.
+        let rotated_cube = Cube::solved().sequence_moves("R L'".parse::<Macro>().unwrap().move_iter());
+        assert!(rotated_cube.is_solved());
+        assert!(rotated_cube != Cube::solved());
+        assert!(!Cube::solved().sequence(Move::R3).is_solved());
+
+        // Repeating "R U" returns to the solved cube after 15 repetitions (105 on a 3x3 cube, because of the
+        // edges), and not before.
+        let m: Macro = "R U".parse().unwrap();
+        let mut visited_cubes = HashSet::new();
+        let mut cube = Cube::solved();
+        while visited_cubes.insert(cube.clone()) {
+            cube = cube.sequence_moves(m.move_iter());
+        }
+        assert_eq!(cube, Cube::solved());
+        assert_eq!(visited_cubes.len(), 15);
+    }
+
+    #[test]
+    fn positions_orientations_round_trip() {
+        let scrambled_cube = Cube::solved().sequence_moves("F R' U2 L D2 B".parse::<Macro>().unwrap().move_iter());
+        for cube in [Cube::solved(), scrambled_cube].iter() {
+            for idx in 0..18u8 {
+                let moved_cube = cube.sequence(Move::from(idx));
+                let (positions, orientations) = moved_cube.positions_orientations();
+                let built_cube = Cube::from_positions_orientations(positions, orientations).unwrap();
+                assert_eq!(built_cube, moved_cube);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_positions_orientations() {
+        let positions = [0, 1, 2, 3, 4, 5, 6, 7];
+        let orientations = [0; 8];
+        assert_eq!(
+            Cube::from_positions_orientations([0, 1, 2, 3, 4, 5, 6, 8], orientations).err(),
+            Some(StateError::PositionOutOfRange{position_idx: 7, position: 8})
+        );
+        assert_eq!(
+            Cube::from_positions_orientations([0, 1, 2, 3, 2, 5, 6, 7], orientations).err(),
+            Some(StateError::RepeatedPosition{position: 2})
+        );
+        assert_eq!(
+            Cube::from_positions_orientations(positions, [0, 0, 3, 0, 0, 0, 0, 0]).err(),
+            Some(StateError::OrientationOutOfRange{position_idx: 2, orientation: 3})
+        );
+        assert_eq!(
+            Cube::from_positions_orientations(positions, [0, 1, 1, 0, 0, 0, 0, 2]).err(),
+            Some(StateError::TwistedCorners{twist: 1})
+        );
+    }
+
+    #[test]
+    fn clockwise_turn_moves_front_to_up() {
+        // In standard notation R moves the stickers on the front side up.
+        let solved = Cube::solved();
+        let cube = solved.sequence("R".parse().unwrap());
+        let transform = cube.transform([1, 1, 1]).inverse();
+        assert_eq!(normal_side(transform.apply(&normal(Side::U))), Side::F);
+    }
+}
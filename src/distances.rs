@@ -0,0 +1,388 @@
+use std;
+use std::io::Read;
+use std::io::Write;
+use cube::Move;
+use coordinates;
+use coordinates::Coordinates;
+use coordinates::MoveTables;
+use optimal;
+use optimal::Metric;
+
+/// The number of states with cubie 0 at position 0 in orientation 0. The left, down and back sides are never
+/// turned, so these are all the states up to whole-cube rotation.
+pub const STATE_COUNT: usize = coordinates::FIXED_CORNER_PERMUTATION_COUNT * FIXED_CORNER_ORIENTATION_COUNT;
+
+// Orientation coordinates with position 0 in orientation 0 are the lowest, since position 0 is the most
+// significant digit.
+const FIXED_CORNER_ORIENTATION_COUNT: usize = coordinates::ORIENTATION_COUNT / 3;
+
+// The file starts with these bytes, followed by the version, the metric, the number of states and the
+// checksum of those three numbers and the entries, each as a little-endian 32-bit number, and then the entries.
+const MAGIC: &[u8; 8] = b"R2X2DIST";
+const VERSION: u32 = 1;
+const HEADER_LENGTH: usize = 8 + 4*4;
+
+// The entry of a state that has not been reached yet, while generating.
+const UNVISITED: u8 = 3;
+
+/// The distance from every state to the solved state, in some metric. Each state takes 2 bits, holding the
+/// distance modulo 3, which is enough to tell which neighbours are closer, since those are one move closer.
+pub struct DistanceTable {
+    metric: Metric,
+    entries: Vec<u8>,
+}
+
+/// A reason why a distance table could not be read.
+#[derive(Debug)]
+pub enum TableReadingError {
+    FailedToRead{error: std::io::Error},
+    NotADistanceTable,
+    UnsupportedVersion{version: u32},
+    UnknownMetric{metric: u32},
+    WrongLength{expected_state_count: usize, state_count: usize, length: usize},
+    ChecksumMismatch{expected_checksum: u32, checksum: u32},
+    // No move from the state at 'state_idx' leads to a state one move closer to the solved state.
+    InconsistentEntries{state_idx: usize},
+}
+
+impl TableReadingError {
+    pub fn message(&self) -> String {
+        use self::TableReadingError::*;
+        match self {
+            FailedToRead{error} =>
+                format!("failed to read the distance table: {}", error),
+            NotADistanceTable =>
+                String::from("the file is not a distance table"),
+            UnsupportedVersion{version} =>
+                format!("the distance table has version {}, but only version {} is supported", version, VERSION),
+            UnknownMetric{metric} =>
+                format!("the distance table has an unknown metric: {}", metric),
+            WrongLength{expected_state_count, state_count, length} =>
+                format!("the distance table should have {} states, but its header says {} and it has {} bytes",
+                        expected_state_count, state_count, length),
+            ChecksumMismatch{expected_checksum, checksum} =>
+                format!("the distance table is corrupted: its checksum is {:08x}, but should be {:08x}",
+                        checksum, expected_checksum),
+            InconsistentEntries{state_idx} =>
+                format!("the distance table is inconsistent: no move from state {} leads closer to the solved state",
+                        state_idx),
+        }
+    }
+}
+
+// The moves that are searched in 'metric', which leave cubie 0 in place.
+pub(crate) fn generators(metric: Metric) -> Vec<Move> {
+    let mut generators = Vec::new();
+    for &side in [::cube::Side::R, ::cube::Side::U, ::cube::Side::F].iter() {
+        for c in 0..3u8 {
+            // In the quarter-turn metric, half turns are found as two consecutive quarter turns.
+            if metric == Metric::QuarterTurn && c == 1 {
+                continue;
+            }
+            generators.push(Move::from(3*(side as u8) + c));
+        }
+    }
+    generators
+}
+
+fn state_idx(coordinates: Coordinates) -> usize {
+    assert!((coordinates.permutation as usize) < coordinates::FIXED_CORNER_PERMUTATION_COUNT);
+    assert!((coordinates.orientation as usize) < FIXED_CORNER_ORIENTATION_COUNT);
+    coordinates.permutation as usize * FIXED_CORNER_ORIENTATION_COUNT + coordinates.orientation as usize
+}
+
+fn state_coordinates(state_idx: usize) -> Coordinates {
+    Coordinates {
+        permutation: (state_idx / FIXED_CORNER_ORIENTATION_COUNT) as u16,
+        orientation: (state_idx % FIXED_CORNER_ORIENTATION_COUNT) as u16,
+    }
+}
+
+fn packed_entry(entries: &[u8], state_idx: usize) -> u8 {
+    (entries[state_idx / 4] >> (2*(state_idx % 4))) & 3
+}
+
+fn set_packed_entry(entries: &mut [u8], state_idx: usize, entry: u8) {
+    let shift = 2*(state_idx % 4);
+    let byte = &mut entries[state_idx / 4];
+    *byte = (*byte & !(3 << shift)) | (entry << shift);
+}
+
+// Finds the distance modulo 3 from state 0 to every state, packed into 2 bits per state, and counts the
+// states at each distance. 'neighbour' returns the state that a move leads to.
+fn breadth_first_search<F>(state_count: usize, generators: &[Move], neighbour: F) -> (Vec<u8>, Vec<usize>)
+where
+    F: Fn(usize, Move) -> usize
+{
+    let mut entries = vec![0xff; state_count.div_ceil(4)];
+    set_packed_entry(&mut entries, 0, 0);
+    let mut counts = vec![1];
+    loop {
+        let distance = counts.len();
+        let frontier_entry = ((distance - 1) % 3) as u8;
+        let mut count = 0;
+        // The states that match the frontier entry include some that are 3, 6, ... moves closer, but all
+        // their neighbours have been visited already, so expanding them again does no harm.
+        for idx in 0..state_count {
+            if packed_entry(&entries, idx) != frontier_entry {
+                continue;
+            }
+            for &m in generators.iter() {
+                let next_idx = neighbour(idx, m);
+                if packed_entry(&entries, next_idx) == UNVISITED {
+                    set_packed_entry(&mut entries, next_idx, (distance % 3) as u8);
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return (entries, counts);
+        }
+        counts.push(count);
+    }
+}
+
+/// Returns the number of states at each distance from the solved state in 'metric'. With 'fixed_corner',
+/// these are the states with cubie 0 at position 0 in orientation 0, turning only the right, up and front
+/// sides. Otherwise, these are all the states, turning any side, and only the solved state in its original
+/// orientation counts as solved.
+pub fn depth_counts(metric: Metric, fixed_corner: bool) -> Vec<usize> {
+    let tables = MoveTables::get();
+    if fixed_corner {
+        let (_, counts) = breadth_first_search(STATE_COUNT, &generators(metric), |idx, m| {
+            state_idx(state_coordinates(idx).apply(m, tables))
+        });
+        return counts;
+    }
+    let generators: Vec<Move> = (0..18u8)
+        .filter(|move_idx| metric == Metric::HalfTurn || move_idx % 3 != 1)
+        .map(Move::from)
+        .collect();
+    let state_count = coordinates::PERMUTATION_COUNT * coordinates::ORIENTATION_COUNT;
+    let (_, counts) = breadth_first_search(state_count, &generators, |idx, m| {
+        let permutation = tables.permutation[idx / coordinates::ORIENTATION_COUNT][m as usize] as usize;
+        let orientation = tables.orientation[idx % coordinates::ORIENTATION_COUNT][m as usize] as usize;
+        permutation * coordinates::ORIENTATION_COUNT + orientation
+    });
+    counts
+}
+
+// CRC-32, as used by zip and PNG, of the bytes of each part in turn.
+fn checksum(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {(crc >> 1) ^ 0xedb88320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+impl DistanceTable {
+    /// Finds the distance of every state with a breadth-first search from the solved state.
+    pub fn generate(metric: Metric) -> DistanceTable {
+        let tables = MoveTables::get();
+        let (entries, _) = breadth_first_search(STATE_COUNT, &generators(metric), |idx, m| {
+            state_idx(state_coordinates(idx).apply(m, tables))
+        });
+        DistanceTable {metric, entries}
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    fn entry(&self, state_idx: usize) -> u8 {
+        packed_entry(&self.entries, state_idx)
+    }
+
+    // The entry of the state with 'coordinates', with cubie 0 home.
+    pub(crate) fn coordinates_entry(&self, coordinates: Coordinates) -> u8 {
+        self.entry(state_idx(coordinates))
+    }
+
+    /// Returns a shortest solution of the state with cubie 0 at position 0 in orientation 0, and the other
+    /// cubies at 'positions' (indexed by cubie) in 'orientations' (indexed by position). Quarter turns found in
+    /// the quarter-turn metric are merged into half turns. Fails if the entries of the table do not lead to the
+    /// solved state.
+    pub fn fixed_corner_solution(&self, positions: [u8; 8], orientations: [u8; 8])
+        -> Result<Vec<Move>, TableReadingError>
+    {
+        assert!(positions[0] == 0 && orientations[0] == 0);
+        let coordinates = Coordinates {
+            permutation: coordinates::permutation_rank(&positions),
+            orientation: coordinates::orientation_coordinate(&orientations),
+        };
+        self.closer_moves(coordinates).map(optimal::merge_quarter_turns)
+    }
+
+    /// Returns the distance from 'cube' to the solved state, up to a whole-cube rotation, in the metric of the
+    /// table. Fails if the entries of the table do not lead to the solved state.
+    pub fn distance(&self, cube: &::cube::Cube) -> Result<usize, TableReadingError> {
+        self.closer_moves(Coordinates::from_cube(&cube.normalized(0))).map(|moves| moves.len())
+    }
+
+    // Returns moves from 'generators' that lead from 'coordinates' (with cubie 0 home) to the solved state, each
+    // one move closer. In the quarter-turn metric, these are all quarter turns. Fails if some state on the way
+    // has no closer neighbour, which a table that was generated and read correctly never has.
+    fn closer_moves(&self, start_coordinates: Coordinates) -> Result<Vec<Move>, TableReadingError> {
+        let tables = MoveTables::get();
+        let generators = generators(self.metric);
+        let mut coordinates = start_coordinates;
+        let mut moves = Vec::new();
+        while coordinates != (Coordinates {permutation: 0, orientation: 0}) {
+            // The neighbours that are one move closer are the ones whose distance is one less, modulo 3. A
+            // corrupted table could also lead around in circles, but no path is longer than the number of states.
+            let closer_entry = (self.entry(state_idx(coordinates)) + 2) % 3;
+            let optional_move = generators.iter().find(|&&m| {
+                self.entry(state_idx(coordinates.apply(m, tables))) == closer_entry
+            });
+            match optional_move {
+                Some(&m) if moves.len() < STATE_COUNT => {
+                    moves.push(m);
+                    coordinates = coordinates.apply(m, tables);
+                },
+                _ => return Err(TableReadingError::InconsistentEntries{state_idx: state_idx(coordinates)}),
+            }
+        }
+        Ok(moves)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let metric_idx: u32 = match self.metric {Metric::HalfTurn => 0, Metric::QuarterTurn => 1};
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.entries.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&metric_idx.to_le_bytes());
+        bytes.extend_from_slice(&(STATE_COUNT as u32).to_le_bytes());
+        let checksum = checksum(&[&bytes[8..HEADER_LENGTH - 4], &self.entries]);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes.extend_from_slice(&self.entries);
+        bytes
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<DistanceTable, TableReadingError> {
+        use self::TableReadingError::*;
+        if bytes.len() < HEADER_LENGTH || &bytes[0..8] != MAGIC {
+            return Err(NotADistanceTable);
+        }
+        let header_number = |idx: usize| {
+            let offset = 8 + 4*idx;
+            u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+        };
+        let version = header_number(0);
+        if version != VERSION {
+            return Err(UnsupportedVersion{version});
+        }
+        let metric = {
+            match header_number(1) {
+                0 => Metric::HalfTurn,
+                1 => Metric::QuarterTurn,
+                metric => return Err(UnknownMetric{metric}),
+            }
+        };
+        let state_count = header_number(2) as usize;
+        if state_count != STATE_COUNT || bytes.len() != HEADER_LENGTH + STATE_COUNT.div_ceil(4) {
+            return Err(WrongLength{expected_state_count: STATE_COUNT, state_count, length: bytes.len()});
+        }
+        let entries = &bytes[HEADER_LENGTH..];
+        let expected_checksum = header_number(3);
+        let checksum = checksum(&[&bytes[8..HEADER_LENGTH - 4], entries]);
+        if checksum != expected_checksum {
+            return Err(ChecksumMismatch{expected_checksum, checksum});
+        }
+        Ok(DistanceTable {metric, entries: entries.to_vec()})
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(&self.serialize())?;
+        file.sync_all()
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<DistanceTable, TableReadingError> {
+        let mut bytes = Vec::new();
+        let result = std::fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes));
+        if let Err(error) = result {
+            return Err(TableReadingError::FailedToRead{error});
+        }
+        DistanceTable::deserialize(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_match_search() {
+        let table = DistanceTable::generate(Metric::HalfTurn);
+        assert!((0..STATE_COUNT).all(|idx| table.entry(idx) != UNVISITED));
+        for scramble in ["R U F", "R U2 F' R2 U F2 R' U'", "F2 U' R U' F R2 U F'"].iter() {
+            let cube = ::cube::Cube::solved().sequence_moves(scramble.parse::<::cube::Macro>().unwrap().move_iter());
+            let (positions, orientations) = cube.positions_orientations();
+            let positions = ::permutations::inverse(positions);
+            let moves = table.fixed_corner_solution(positions, orientations).unwrap();
+            assert!(cube.sequence_moves(moves.iter()).is_solved());
+            assert_eq!(moves.len(), optimal::fixed_corner_solution(positions, orientations, Metric::HalfTurn).len());
+        }
+    }
+
+    #[test]
+    fn depth_counts_match_published_counts() {
+        assert_eq!(
+            depth_counts(Metric::HalfTurn, true),
+            vec![1, 9, 54, 321, 1847, 9992, 50136, 227536, 870072, 1887748, 623800, 2644]
+        );
+        assert_eq!(
+            depth_counts(Metric::QuarterTurn, true),
+            vec![1, 6, 27, 120, 534, 2256, 8969, 33058, 114149, 360508, 930588, 1350852, 782536, 90280, 276]
+        );
+    }
+
+    #[test]
+    fn corrupted_tables_are_rejected() {
+        let table = DistanceTable {metric: Metric::QuarterTurn, entries: vec![0x1b; STATE_COUNT.div_ceil(4)]};
+        let bytes = table.serialize();
+        let read_table = DistanceTable::deserialize(&bytes).unwrap();
+        assert!(read_table.metric() == Metric::QuarterTurn && read_table.entries == table.entries);
+
+        let mut corrupted_bytes = bytes.clone();
+        corrupted_bytes[HEADER_LENGTH + 1000] ^= 4;
+        match DistanceTable::deserialize(&corrupted_bytes) {
+            Err(TableReadingError::ChecksumMismatch{..}) => {},
+            _ => panic!(),
+        }
+        match DistanceTable::deserialize(&bytes[0..bytes.len() - 1]) {
+            Err(TableReadingError::WrongLength{..}) => {},
+            _ => panic!(),
+        }
+        // A flipped bit in the header could turn a table into one for the other metric.
+        let mut other_metric_bytes = bytes.clone();
+        other_metric_bytes[12] ^= 1;
+        match DistanceTable::deserialize(&other_metric_bytes) {
+            Err(TableReadingError::ChecksumMismatch{..}) => {},
+            _ => panic!(),
+        }
+        let mut newer_bytes = bytes.clone();
+        newer_bytes[8] = 2;
+        match DistanceTable::deserialize(&newer_bytes) {
+            Err(TableReadingError::UnsupportedVersion{version}) => assert_eq!(version, 2),
+            _ => panic!(),
+        }
+        match DistanceTable::deserialize(b"R2X2") {
+            Err(TableReadingError::NotADistanceTable) => {},
+            _ => panic!(),
+        }
+
+        // Every entry says the distance is a multiple of 3, so no state but the solved one has a closer neighbour.
+        let inconsistent_table = DistanceTable {metric: Metric::HalfTurn, entries: vec![0; STATE_COUNT.div_ceil(4)]};
+        let cube = ::cube::Cube::solved().sequence(Move::R3);
+        match inconsistent_table.distance(&cube) {
+            Err(TableReadingError::InconsistentEntries{..}) => {},
+            _ => panic!(),
+        }
+        assert_eq!(inconsistent_table.distance(&::cube::Cube::solved()).unwrap(), 0);
+    }
+}
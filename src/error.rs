@@ -1,11 +1,10 @@
-use std::ptr::null_mut;
-
 #[cfg(windows)]
 pub fn notify_error(message: &String)
 {
     use std::ffi::OsStr;
     use std::iter::once;
     use std::os::windows::ffi::OsStrExt;
+    use std::ptr::null_mut;
     use winapi::um::winuser::*;
     let title = "Error";
     let title_wide: Vec<u16> = OsStr::new(title).encode_wide().chain(once(0)).collect();
@@ -15,6 +14,7 @@ pub fn notify_error(message: &String)
     };
 }
 
+#[cfg(windows)]
 pub fn report_fatal_error_and_exit(message: &String)
 {
     use ::std::process::exit;
@@ -0,0 +1,44 @@
+//! Representation and solving of the 2x2 Rubik's cube.
+//!
+//! A [`cube::Cube`] is built from the stickers on its sides with [`cube::Cube::from_stickers`], and moved with
+//! [`cube::Cube::sequence`] and [`cube::Cube::sequence_moves`]. Its state can be queried with
+//! [`cube::Cube::positions_orientations`], [`cube::Cube::transform`] and [`cube::Cube::sticker`]. The whole cube
+//! is rotated with [`cube::Cube::rotate`], and [`cube::Cube::normalized`] rotates it to bring a chosen corner
+//! home.
+//!
+//! There are two solvers: [`solver::solution`] quickly chains fixed macros that swap and twist corners, and
+//! [`optimal::solution`] finds a shortest solution in the half-turn or quarter-turn metric.
+//! [`optimal::transition`] finds a shortest sequence of moves from one cube to another.
+//!
+//! For searching, [`coordinates::Coordinates`] encodes a state as a permutation rank and an orientation
+//! coordinate, and [`coordinates::MoveTables`] turns moves into table lookups.
+//! [`verification::verify_macro_solver`] enumerates every state by its coordinates to check the macro solver on
+//! it, and [`distances::DistanceTable`] stores the distance of every state, so that [`optimal::table_solution`]
+//! can find shortest solutions without searching. [`distances::depth_counts`] counts the states at each distance.
+//! [`optimal::restricted_solution`] only turns chosen sides, and [`optimal::cheapest_solution`] finds the
+//! solution with the lowest total of the [`costs::MoveCosts`] of its moves. [`methods::ortega_solution`] and
+//! [`methods::beginner_solution`] solve the way people do, in named steps such as orienting the last layer.
+//!
+//! [`scramble::random_state_scramble`] generates scrambles for random states, using the seedable generator in
+//! [`random`].
+//!
+//! [`ui::run_main_loop`] pages through a solution on any [`ui::Console`], such as the [`ui::ScriptedConsole`],
+//! which plays back fixed inputs and records what would be shown.
+
+// The original modules prefer explicit returns, index loops, and spelling out digits and bit operations such as
+// '>> 0' for symmetry with their neighbours.
+#[allow(clippy::needless_return, clippy::needless_range_loop, clippy::identity_op, clippy::zero_prefixed_literal,
+        clippy::redundant_field_names, clippy::match_ref_pats)]
+pub mod cube;
+pub mod permutations;
+#[allow(clippy::needless_return)]
+pub mod solver;
+pub mod optimal;
+pub mod coordinates;
+pub mod verification;
+pub mod distances;
+pub mod costs;
+pub mod methods;
+pub mod random;
+pub mod scramble;
+pub mod ui;
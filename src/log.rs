@@ -1,4 +1,6 @@
+#[cfg(windows)]
 use winapi;
+#[cfg(windows)]
 use error;
 
 pub fn log(message: &String)
@@ -6,6 +8,7 @@ pub fn log(message: &String)
     eprintln!("{}", message);
 }
 
+#[cfg(windows)]
 pub fn log_get_last_error_code_and_exit(file_name: &str, line: u32, column: u32)
 {
     use ::std::process::exit;
@@ -1,47 +1,285 @@
-// TODO: Wrap up most things except main into a rubik2x2 crate, instead of gathering everything here.
+#[cfg(windows)]
 extern crate winapi;
+#[cfg(unix)]
+extern crate libc;
+extern crate rubik2x2;
 use std::env;
-mod ui;
+use rubik2x2::cube;
+use rubik2x2::solver;
+use rubik2x2::optimal;
+use rubik2x2::verification;
+use rubik2x2::distances;
+use rubik2x2::costs;
+use rubik2x2::methods;
+use rubik2x2::ui;
+#[allow(clippy::needless_return, clippy::needless_range_loop, clippy::redundant_field_names, clippy::match_ref_pats,
+        clippy::needless_borrowed_reference, clippy::question_mark)]
 mod cli;
-mod cube;
 mod error;
 mod log;
-mod solver;
-mod permutations;
+mod output;
+#[cfg(windows)]
+#[allow(clippy::needless_return, clippy::needless_range_loop, clippy::match_ref_pats)]
+mod windows_console;
+#[cfg(unix)]
+mod ansi_console;
+#[cfg(windows)]
+use windows_console::ConsoleContext;
+#[cfg(unix)]
+use ansi_console::ConsoleContext;
 
-fn report_error_and_exit(message: &String) -> !
+// Exit statuses, so that scripts can tell failures apart.
+const INVALID_INPUT_EXIT_CODE: i32 = 1;
+const UNSOLVABLE_EXIT_CODE: i32 = 2;
+const CONSOLE_ERROR_EXIT_CODE: i32 = 3;
+const VERIFICATION_FAILED_EXIT_CODE: i32 = 4;
+const TABLE_ERROR_EXIT_CODE: i32 = 5;
+
+fn report_error_and_exit(message: &String, exit_code: i32) -> !
 {
-    println!("{}", message);
-    ::std::process::exit(1);
+    eprintln!("{}", message);
+    ::std::process::exit(exit_code);
 }
 
 fn main()
 {
-    // TODO: Run tests in a test suite instead.
-    solver::test();
-    
-    let console_context =
+    // Read the arguments first, so that the console is left untouched if they are invalid.
+    let command =
     {
-        match ui::ConsoleContext::try_initialize()
+        match cli::try_read_command(&mut env::args().skip(1))
         {
-            Ok(context) => context,
-            Err(error) => report_error_and_exit(&error.message()),
+            Ok(command) => command,
+            Err(error) =>
+            {
+                let exit_code = if error.is_unsolvable() {UNSOLVABLE_EXIT_CODE} else {INVALID_INPUT_EXIT_CODE};
+                report_error_and_exit(&error.message(), exit_code)
+            },
         }
     };
-    
-    let input =
+
+    match command
+    {
+        cli::Command::Solve(input) => solve(*input),
+        cli::Command::VerifyMacroSolver => verify_macro_solver(),
+        cli::Command::BuildTable{metric, path} => build_table(metric, &path),
+        cli::Command::DepthDistribution{output_format} => print_depth_distribution(output_format),
+    }
+}
+
+fn metric_name(metric: optimal::Metric) -> &'static str
+{
+    match metric
+    {
+        optimal::Metric::HalfTurn => "half-turn",
+        optimal::Metric::QuarterTurn => "quarter-turn",
+    }
+}
+
+fn build_table(metric: optimal::Metric, path: &str)
+{
+    eprintln!("Building the distance table for the {} metric", metric_name(metric));
+    let table = distances::DistanceTable::generate(metric);
+    if let Err(error) = table.save(std::path::Path::new(path))
+    {
+        report_error_and_exit(&format!("Failed to write the distance table to {}: {}", path, error),
+                              TABLE_ERROR_EXIT_CODE);
+    }
+}
+
+fn print_depth_distribution(output_format: Option<output::Format>)
+{
+    let mut columns = Vec::new();
+    for &metric in [optimal::Metric::HalfTurn, optimal::Metric::QuarterTurn].iter() {
+        for &fixed_corner in [true, false].iter() {
+            let states = if fixed_corner {"the states with a fixed corner"} else {"all states"};
+            eprintln!("Counting {} at each distance in the {} metric", states, metric_name(metric));
+            let counts = distances::depth_counts(metric, fixed_corner);
+            columns.push(output::DepthCounts {metric, fixed_corner, counts});
+        }
+    }
+    print!("{}", output::format_depth_counts(&columns, output_format.unwrap_or(output::Format::Plain)));
+}
+
+fn load_table(path: &str, metric: optimal::Metric) -> distances::DistanceTable
+{
+    let table =
     {
-        match cli::try_read_arguments(&mut env::args().skip(1))
+        match distances::DistanceTable::load(std::path::Path::new(path))
         {
-            Ok(input) => input,
-            Err(error) => report_error_and_exit(&error.message()),
+            Ok(table) => table,
+            Err(error) => report_error_and_exit(&format!("{}: {}", path, error.message()), TABLE_ERROR_EXIT_CODE),
+        }
+    };
+    if table.metric() != metric
+    {
+        report_error_and_exit(
+            &format!("{}: the distance table is for the {} metric, but the {} metric was asked for",
+                     path, metric_name(table.metric()), metric_name(metric)),
+            TABLE_ERROR_EXIT_CODE
+        );
+    }
+    table
+}
+
+// Returns the result of using a distance table, or exits if its entries turned out to be inconsistent. 'path' is
+// where the table was loaded from, if it was not generated.
+fn checked_table_result<T>(result: Result<T, distances::TableReadingError>, path: Option<&String>) -> T
+{
+    match result
+    {
+        Ok(value) => value,
+        Err(error) =>
+        {
+            let source = path.map(|path| path.as_str()).unwrap_or("generated table");
+            report_error_and_exit(&format!("{}: {}", source, error.message()), TABLE_ERROR_EXIT_CODE)
+        },
+    }
+}
+
+fn verify_macro_solver()
+{
+    let thread_count = std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+    eprintln!("Verifying the macro solver on every state, using {} threads", thread_count);
+    match verification::verify_macro_solver(thread_count)
+    {
+        Ok(report) => print!("{}", output::format_verification_report(&report)),
+        Err(failure) => report_error_and_exit(&format!("Verification failed: {}", failure.message()),
+                                              VERIFICATION_FAILED_EXIT_CODE),
+    }
+}
+
+// Prints the cheapest solution with the costs in 'costs_path' next to the shortest solution.
+fn compare_costs(input: &cli::Input, costs_path: &str)
+{
+    let costs =
+    {
+        match costs::MoveCosts::load(std::path::Path::new(costs_path))
+        {
+            Ok(costs) => costs,
+            Err(error) => report_error_and_exit(&format!("{}: {}", costs_path, error.message()),
+                                                INVALID_INPUT_EXIT_CODE),
+        }
+    };
+    let table =
+    {
+        match input.table_path
+        {
+            Some(ref path) => load_table(path, input.metric),
+            None => distances::DistanceTable::generate(input.metric),
+        }
+    };
+    let shortest_moves =
+        checked_table_result(optimal::table_solution(&input.initial_cube, &table), input.table_path.as_ref());
+    // Simplifying could merge turns into more expensive ones, so the cheapest solution is left as it is.
+    let cheapest_moves = checked_table_result(optimal::cheapest_solution(&input.initial_cube, &costs, &table),
+                                              input.table_path.as_ref());
+    let format = input.output_format.unwrap_or(output::Format::Plain);
+    print!("{}", output::format_cost_comparison(&cheapest_moves, &shortest_moves, &costs, input.metric, format));
+}
+
+fn solve(input: cli::Input)
+{
+    if let Some(ref costs_path) = input.costs_path {
+        compare_costs(&input, costs_path);
+        return;
+    }
+    match input.solver
+    {
+        cli::Solver::Ortega => return solve_in_steps(&input, methods::ortega_solution(&input.initial_cube)),
+        cli::Solver::Beginner => return solve_in_steps(&input, methods::beginner_solution(&input.initial_cube)),
+        cli::Solver::Optimal | cli::Solver::Macro => {},
+    }
+    let unsimplified_solution_moves =
+    {
+        match input.solver
+        {
+            cli::Solver::Optimal if input.target_cube.is_some() =>
+                optimal::transition(&input.initial_cube, input.target_cube.as_ref().unwrap(), input.metric),
+            cli::Solver::Optimal if input.move_set.is_some() =>
+            {
+                let move_set = input.move_set.as_ref().unwrap();
+                match optimal::restricted_solution(&input.initial_cube, move_set, input.metric)
+                {
+                    Some(moves) => moves,
+                    None => report_error_and_exit(
+                        &format!("Unreachable state: the cube can not be solved using only the moves {}", move_set),
+                        UNSOLVABLE_EXIT_CODE
+                    ),
+                }
+            },
+            cli::Solver::Optimal =>
+            {
+                match input.table_path
+                {
+                    Some(ref path) =>
+                    {
+                        let table = load_table(path, input.metric);
+                        checked_table_result(optimal::table_solution(&input.initial_cube, &table), Some(path))
+                    },
+                    None => optimal::metric_solution(&input.initial_cube, input.metric),
+                }
+            },
+            cli::Solver::Macro => solver::solution(&input.initial_cube),
+            cli::Solver::Ortega | cli::Solver::Beginner => unreachable!(),
+        }
+    };
+    // Simplifying a restricted solution could merge turns into ones that are not allowed, such as three clockwise
+    // turns into one counter-clockwise turn, and the search does not leave anything to simplify anyway.
+    let solution_moves =
+    {
+        if input.move_set.is_some() {
+            unsimplified_solution_moves.clone()
+        } else {
+            cube::Macro::from(unsimplified_solution_moves.as_slice()).simplified().moves
+        }
+    };
+    let simplification_saved_moves_count = unsimplified_solution_moves.len() - solution_moves.len();
+
+    if let Some(format) = input.output_format {
+        print!("{}", output::format_solution(&solution_moves, simplification_saved_moves_count, format));
+        return;
+    }
+
+    view_solution(&input, &ui::pages_of_moves(&solution_moves), simplification_saved_moves_count);
+}
+
+// Prints or shows a solution that follows a method, with a page for each step. The steps are not simplified, so
+// that each one still shows the algorithm for its case.
+fn solve_in_steps(input: &cli::Input, steps: Vec<methods::Step>)
+{
+    if let Some(format) = input.output_format {
+        print!("{}", output::format_steps(&steps, format));
+        return;
+    }
+    let sections: Vec<ui::Section> =
+        steps.into_iter().map(|step| {
+            let heading = format!("{}\n{}", step.name, step.explanation);
+            ui::Section {heading: Some(heading), moves: step.moves}
+        }).collect();
+    view_solution(input, &sections, 0);
+}
+
+fn view_solution(input: &cli::Input, sections: &[ui::Section], simplification_saved_moves_count: usize)
+{
+    let mut console_context =
+    {
+        match ConsoleContext::try_initialize()
+        {
+            Ok(context) => context,
+            Err(error) => report_error_and_exit(&error.message(), CONSOLE_ERROR_EXIT_CODE),
         }
     };
     
-    let solution_moves = solver::solution(&input.initial_cube);
-    ui::run_main_loop(&input.aliases, &input.initial_cube, &console_context, &solution_moves);
+    ui::run_main_loop(&input.aliases, &input.initial_cube, &mut console_context, sections,
+                      simplification_saved_moves_count);
+    // The error is only reported after the console is restored, so that the terminal is usable again.
+    let optional_error = ui::Console::take_error(&mut console_context);
     if let Some(error) = console_context.try_deinitialize()
     {
         log::log(&error.message());
     }
+    if let Some(message) = optional_error
+    {
+        report_error_and_exit(&message, CONSOLE_ERROR_EXIT_CODE);
+    }
 }
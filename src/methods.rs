@@ -0,0 +1,700 @@
+use cube;
+use permutations;
+use cube::Cube;
+use cube::Move;
+use cube::Side;
+use cube::Token;
+
+/// A stage of a solution that follows a method people learn, named after the case that was recognised.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub name: String,
+    // What the step achieves, in a sentence or two.
+    pub explanation: String,
+    // The moves, with any whole-cube rotations that the method calls for written as rotations.
+    pub moves: cube::Macro,
+}
+
+// Orientation algorithms for the up side, named after the cases they solve.
+const OLL_ALGORITHMS: [(&str, &str); 7] = [
+    ("Sune", "R U R' U R U2 R'"),
+    ("Antisune", "R U2 R' U' R U' R'"),
+    ("H", "R2 U2 R U2 R2"),
+    ("Pi", "F R U R' U' R U R' U' F'"),
+    ("U", "F R U R' U' F'"),
+    ("T", "R U R' U' R' F R F'"),
+    ("L", "F R' F' R U R U' R'"),
+];
+
+// Permutation algorithms for both layers, named after the cases they solve. The cases with a diagonal swap on
+// top and an adjacent swap on the bottom, or a swap on the bottom only, are solved with these algorithms
+// upside down.
+const PBL_ALGORITHMS: [(LayerPermutation, LayerPermutation, &str); 5] = [
+    (LayerPermutation::AdjacentSwap, LayerPermutation::Solved, "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    (LayerPermutation::DiagonalSwap, LayerPermutation::Solved, "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    (LayerPermutation::AdjacentSwap, LayerPermutation::AdjacentSwap, "R2 U' B2 U2 R2 U' R2"),
+    (LayerPermutation::DiagonalSwap, LayerPermutation::DiagonalSwap, "R2 F2 R2"),
+    (LayerPermutation::AdjacentSwap, LayerPermutation::DiagonalSwap, "R U' R F2 R' U R'"),
+];
+
+/// How the corners of a layer whose side is done are placed relative to each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayerPermutation {
+    // Solved, up to turning the layer.
+    Solved,
+    // Two neighbouring corners are swapped.
+    AdjacentSwap,
+    // Two opposite corners are swapped.
+    DiagonalSwap,
+}
+
+impl LayerPermutation {
+    fn description(&self) -> &'static str {
+        use self::LayerPermutation::*;
+        match *self {
+            Solved => "solved",
+            AdjacentSwap => "adjacent swap",
+            DiagonalSwap => "diagonal swap",
+        }
+    }
+}
+
+fn parse_moves(notation: &str) -> Vec<Move> {
+    notation.parse::<cube::Macro>().unwrap().moves
+}
+
+// Returns the turns of 'side' that leave a layer as it is, or turn it once, twice or back once.
+fn layer_turns(side: Side) -> [Vec<Move>; 4] {
+    let side_idx = side as u8;
+    [vec![], vec![Move::from(3*side_idx + 2)], vec![Move::from(3*side_idx)], vec![Move::from(3*side_idx + 1)]]
+}
+
+// The corner positions on 'side', with coordinates that are each -1 or 1.
+fn side_positions(side: Side) -> Vec<[i32; 3]> {
+    let normal = cube::normal(side);
+    let mut positions = Vec::new();
+    for position_idx in 0..8 {
+        let position = [
+            2*(position_idx & 1) - 1,
+            2*((position_idx >> 1) & 1) - 1,
+            2*((position_idx >> 2) & 1) - 1,
+        ];
+        if (0..3).any(|axis_idx| normal[axis_idx] != 0 && normal[axis_idx] == position[axis_idx]) {
+            positions.push(position);
+        }
+    }
+    positions
+}
+
+/// Returns whether every sticker on 'side' belongs on 'side' in the solved cube.
+pub fn is_side_done(cube: &Cube, side: Side) -> bool {
+    side_positions(side).iter().all(|&position| cube.sticker(position, side) == side)
+}
+
+/// Classifies the layer of 'side', whose stickers must all be on 'side', by comparing the two stickers on each
+/// of the neighbouring sides.
+pub fn layer_permutation(cube: &Cube, side: Side) -> LayerPermutation {
+    assert!(is_side_done(cube, side));
+    let layer_positions = side_positions(side);
+    let mut matching_pair_count = 0;
+    for side_idx in 0..6 {
+        let neighbour = Side::from(side_idx);
+        if (side_idx as u8)/2 == (side as u8)/2 {
+            continue;
+        }
+        let stickers: Vec<Side> = side_positions(neighbour).into_iter()
+            .filter(|position| layer_positions.contains(position))
+            .map(|position| cube.sticker(position, neighbour))
+            .collect();
+        if stickers[0] == stickers[1] {
+            matching_pair_count += 1;
+        }
+    }
+    match matching_pair_count {
+        4 => LayerPermutation::Solved,
+        1 => LayerPermutation::AdjacentSwap,
+        0 => LayerPermutation::DiagonalSwap,
+        _ => unreachable!("a layer has {} pairs of matching stickers, but only 0, 1 or 4 are possible",
+                          matching_pair_count),
+    }
+}
+
+// Returns the moves of each step after solving the cube rotated by 'solve', which gets the cube with cubie 0
+// home, turned back to the sides of the cube as it was before the rotations.
+fn normalized_steps<F>(cube: &Cube, solve: F) -> Vec<Step>
+where
+    F: FnOnce(&Cube) -> Vec<Step>
+{
+    let rotations = cube.normalizing_rotations(0);
+    let steps = solve(&cube.rotate_all(rotations.iter()));
+    steps.into_iter().map(|step| {
+        let moves = step.moves.move_iter().map(|m| rotations.iter().rev().fold(*m, |m, r| m.rotated(r.inverse())));
+        Step {name: step.name, explanation: step.explanation, moves: cube::Macro::from(moves.collect::<Vec<Move>>())}
+    }).collect()
+}
+
+/// Solves the cube with the Ortega method: first the down side, then orienting the up side (OLL), and then
+/// permuting both layers (PBL), with algorithms from a fixed table. Cubie 0, where the left, down and back sides
+/// meet, stays in place, and its down sticker sets the colour of the first side. Steps that need no moves are
+/// included, so that every step of the method is shown.
+pub fn ortega_solution(cube: &Cube) -> Vec<Step> {
+    normalized_steps(cube, |cube| {
+        let mut steps = Vec::new();
+        let face_moves = down_side_moves(cube);
+        let mut cube = cube.sequence_moves(face_moves.iter());
+        steps.push(Step {
+            name: String::from("Face"),
+            explanation: String::from("Makes one side a single colour, in any arrangement."),
+            moves: cube::Macro::from(face_moves),
+        });
+
+        let oll_step = oll_step(&cube);
+        cube = cube.sequence_moves(oll_step.moves.move_iter());
+        steps.push(oll_step);
+
+        let pbl_step = pbl_step(&cube);
+        assert!(cube.sequence_moves(pbl_step.moves.move_iter()).is_solved());
+        steps.push(pbl_step);
+        steps
+    })
+}
+
+// Returns a shortest sequence of turns of the right, up and front sides that makes the down side one colour.
+// With cubie 0 in place, this takes at most 5 moves.
+fn down_side_moves(cube: &Cube) -> Vec<Move> {
+    fixed_corner_moves(cube, &|cube: &Cube| is_side_done(cube, Side::D))
+}
+
+// Returns a shortest sequence of turns of the right, up and front sides, which keep cubie 0 in place, after
+// which 'is_done' holds.
+fn fixed_corner_moves(cube: &Cube, is_done: &dyn Fn(&Cube) -> bool) -> Vec<Move> {
+    fn search(cube: &Cube, is_done: &dyn Fn(&Cube) -> bool, remaining_depth: usize, moves: &mut Vec<Move>) -> bool {
+        if is_done(cube) {
+            return true;
+        }
+        if remaining_depth == 0 {
+            return false;
+        }
+        for &side in [Side::R, Side::U, Side::F].iter() {
+            if moves.last().is_some_and(|&m| (m as u8)/3 == side as u8) {
+                continue;
+            }
+            for c in 0..3 {
+                let m = Move::from(3*(side as u8) + c);
+                moves.push(m);
+                if search(&cube.sequence(m), is_done, remaining_depth - 1, moves) {
+                    return true;
+                }
+                moves.pop();
+            }
+        }
+        false
+    }
+    let mut depth = 0;
+    loop {
+        let mut moves = Vec::new();
+        if search(cube, is_done, depth, &mut moves) {
+            return moves;
+        }
+        depth += 1;
+    }
+}
+
+// Returns the step that turns the up side to the algorithm's angle and orients it, with the down side done.
+fn oll_step(cube: &Cube) -> Step {
+    let explanation = String::from("Turns the stickers of the opposite colour to the opposite side.");
+    if is_side_done(cube, Side::U) {
+        return Step {name: String::from("OLL (skip)"), explanation, moves: cube::Macro::identity()};
+    }
+    for pre_moves in layer_turns(Side::U).iter() {
+        for &(name, algorithm) in OLL_ALGORITHMS.iter() {
+            let moves: Vec<Move> = pre_moves.iter().cloned().chain(parse_moves(algorithm)).collect();
+            if is_side_done(&cube.sequence_moves(moves.iter()), Side::U) {
+                return Step {name: format!("OLL ({})", name), explanation, moves: cube::Macro::from(moves)};
+            }
+        }
+    }
+    unreachable!("no OLL algorithm orients the up side");
+}
+
+// Returns the step that solves the cube with the up and down sides done, by turning the layers to the
+// algorithm's angle, applying it, and turning the up layer into place.
+fn pbl_step(cube: &Cube) -> Step {
+    let explanation = String::from("Puts the corners of both layers in place, keeping both sides one colour.");
+    let top = layer_permutation(cube, Side::U);
+    let bottom = layer_permutation(cube, Side::D);
+    let name = {
+        if top == LayerPermutation::Solved && bottom == LayerPermutation::Solved {
+            String::from("PBL (skip)")
+        } else {
+            format!("PBL ({} on top and {} on the bottom)", top.description(), bottom.description())
+        }
+    };
+
+    // Each algorithm, and the same algorithm with the cube upside down.
+    let mut algorithms: Vec<Vec<Move>> = vec![vec![]];
+    for &(_, _, notation) in PBL_ALGORITHMS.iter() {
+        let moves = parse_moves(notation);
+        algorithms.push(moves.iter().map(|m| m.rotated(cube::Rotation::X2)).collect());
+        algorithms.push(moves);
+    }
+    for up_pre_moves in layer_turns(Side::U).iter() {
+        for down_pre_moves in layer_turns(Side::D).iter() {
+            for algorithm in algorithms.iter() {
+                let moves: Vec<Move> =
+                    up_pre_moves.iter().chain(down_pre_moves.iter()).chain(algorithm.iter()).cloned().collect();
+                let permuted_cube = cube.sequence_moves(moves.iter());
+                for post_moves in layer_turns(Side::U).iter() {
+                    if permuted_cube.sequence_moves(post_moves.iter()).is_solved() {
+                        let moves: Vec<Move> = moves.into_iter().chain(post_moves.iter().cloned()).collect();
+                        return Step {name, explanation, moves: cube::Macro::from(moves)};
+                    }
+                }
+            }
+        }
+    }
+    unreachable!("no PBL algorithm solves the permuted layers");
+}
+
+// The beginner's method is worked out on the normalized cube, with cubie 0 home, where the first layer is on the
+// left side and the last layer on the right side, because the orientations of 'Cube::positions_orientations' tell
+// whether a corner's left or right sticker faces the left or right side. The cube is held with the left side down
+// instead, by this rotation, so that the algorithms are written as usual, with the last layer up.
+const BEGINNER_ROTATION: cube::Rotation = cube::Rotation::Z1;
+
+const SUNE: &str = "R U R' U R U2 R'";
+const T_PERMUTATION: &str = "R U R' U' R' F R2 U' R' U' R U R' F'";
+const Y_PERMUTATION: &str = "F R U' R' U' R U R' F' R U R' U' R' F R F'";
+
+// Insert the corner at the front right of the up side into the place below it, by where its sticker that goes on
+// the down side faces. None of them moves the other corners of the down side.
+const CORNER_INSERTIONS: [(&str, &str); 3] = [
+    ("right", "R U R'"),
+    ("front", "F' U' F"),
+    ("up", "R U2 R' U' R U R'"),
+];
+
+// Takes the corner at the front right of the down side out to the up side.
+const CORNER_EXTRACTION: &str = "R U R'";
+
+// Where a corner of the first layer is inserted, at the front right of the down side as the cube is held, and
+// the place above it.
+const INSERTION_POSITION_IDX: u8 = 5;
+const ABOVE_INSERTION_POSITION_IDX: u8 = 7;
+
+// The positions of the first layer, which holds cubie 0, and of the last layer.
+const FIRST_LAYER_POSITIONS: [u8; 4] = [0, 2, 4, 6];
+const LAST_LAYER_POSITIONS: [u8; 4] = [1, 3, 5, 7];
+
+// How the cube is held while following the beginner's method: the rotations from the normalized cube to the cube
+// as it is held, which are BEGINNER_ROTATION and then turns around the up side.
+struct Holding {
+    rotations: Vec<cube::Rotation>,
+}
+
+impl Holding {
+    // Returns the moves on the normalized cube that 'notation' makes on the cube as it is held.
+    fn moves(&self, notation: &str) -> Vec<Move> {
+        parse_moves(notation).iter().map(|&m| self.rotations.iter().rev().fold(m, |m, r| m.rotated(r.inverse())))
+            .collect()
+    }
+
+    // Writes 'moves' on the normalized cube as they are made on the cube as it is held.
+    fn tokens(&self, moves: &[Move]) -> Vec<Token> {
+        moves.iter().map(|&m| Token::Move(self.rotations.iter().fold(m, |m, &r| m.rotated(r)))).collect()
+    }
+
+    // Returns where the corner at 'position_idx' on the normalized cube is on the cube as it is held.
+    fn held_position_idx(&self, position_idx: u8) -> u8 {
+        corner_sides(position_idx).iter().map(|&side| {
+            let held_move = self.rotations.iter().fold(Move::from(3*(side as u8)), |m, &r| m.rotated(r));
+            let held_side_idx = (held_move as u8) / 3;
+            (held_side_idx % 2) << (held_side_idx / 2)
+        }).sum()
+    }
+
+    // Returns the position on the normalized cube of the corner at 'held_position_idx' on the cube as it is held.
+    fn position_idx(&self, held_position_idx: u8) -> u8 {
+        (0..8).find(|&position_idx| self.held_position_idx(position_idx) == held_position_idx).unwrap()
+    }
+
+    // Turns the cube around the up side until the corner at 'position_idx' on the normalized cube is at
+    // 'held_position_idx', and returns the rotation, if one is needed.
+    fn turn_to(&mut self, position_idx: u8, held_position_idx: u8) -> Vec<Token> {
+        use cube::Rotation::*;
+        for optional_rotation in [None, Some(Y3), Some(Y2), Some(Y1)].iter() {
+            let mut turned_holding = Holding {rotations: self.rotations.clone()};
+            turned_holding.rotations.extend(optional_rotation);
+            if turned_holding.held_position_idx(position_idx) == held_position_idx {
+                *self = turned_holding;
+                return optional_rotation.iter().map(|&r| Token::Rotation(r)).collect();
+            }
+        }
+        unreachable!("no turn of the whole cube around the up side brings the corner to the held position");
+    }
+}
+
+// Returns the fewest rotations that turn the cube the same way as 'rotations' together.
+fn shortest_rotations(rotations: &[cube::Rotation]) -> Vec<cube::Rotation> {
+    // A rotation is known by where it turns each side.
+    let turned_moves = |rotations: &[cube::Rotation]| -> Vec<Move> {
+        (0..18u8).map(|idx| rotations.iter().fold(Move::from(idx), |m, &r| m.rotated(r))).collect()
+    };
+    let mut candidates: Vec<Vec<cube::Rotation>> = vec![vec![]];
+    for first_idx in 0..9 {
+        candidates.push(vec![cube::Rotation::from(first_idx)]);
+    }
+    for first_idx in 0..9 {
+        for second_idx in 0..9 {
+            candidates.push(vec![cube::Rotation::from(first_idx), cube::Rotation::from(second_idx)]);
+        }
+    }
+    let target_moves = turned_moves(rotations);
+    candidates.into_iter().find(|candidate| turned_moves(candidate) == target_moves).unwrap()
+}
+
+// The sides of the colours of the corner that belongs at 'position_idx'.
+fn corner_sides(position_idx: u8) -> [Side; 3] {
+    [
+        Side::from((position_idx & 1) as i32),
+        Side::from(2 + ((position_idx >> 1) & 1) as i32),
+        Side::from(4 + ((position_idx >> 2) & 1) as i32),
+    ]
+}
+
+// Names the corner that belongs at 'position_idx' by the sides of its colours, such as "left-up-back".
+fn corner_name(position_idx: u8) -> String {
+    let names: Vec<&str> = corner_sides(position_idx).iter().map(|&side| Side::serialization(side)).collect();
+    names.join("-")
+}
+
+// Returns whether the corner that belongs at 'position_idx' is there, and twisted into place.
+fn is_corner_solved(positions: &[u8; 8], orientations: &[u8; 8], position_idx: u8) -> bool {
+    positions[position_idx as usize] == position_idx && orientations[position_idx as usize] == 0
+}
+
+// Returns where the corner that belongs at 'position_idx' is.
+fn corner_position_idx(cube: &Cube, position_idx: u8) -> u8 {
+    let (positions, _) = cube.positions_orientations();
+    permutations::inverse(positions)[position_idx as usize]
+}
+
+fn oriented_last_layer_corner_count(cube: &Cube) -> usize {
+    let (_, orientations) = cube.positions_orientations();
+    LAST_LAYER_POSITIONS.iter().filter(|&&position_idx| orientations[position_idx as usize] == 0).count()
+}
+
+// Lists the corners that 'moves' take out of their positions or twist, by their colours.
+fn moved_corners_sentence(cube: &Cube, moves: &[Move]) -> String {
+    let (positions, orientations) = cube.positions_orientations();
+    let (moved_positions, moved_orientations) = cube.sequence_moves(moves.iter()).positions_orientations();
+    // Indexed by cubie, so that each cubie is compared with itself.
+    let (cubie_positions, moved_cubie_positions) =
+        (permutations::inverse(positions), permutations::inverse(moved_positions));
+    let names: Vec<String> = (0..8u8).filter(|&cubie_idx| {
+        let (position_idx, moved_position_idx) =
+            (cubie_positions[cubie_idx as usize] as usize, moved_cubie_positions[cubie_idx as usize] as usize);
+        position_idx != moved_position_idx || orientations[position_idx] != moved_orientations[moved_position_idx]
+    }).map(corner_name).collect();
+    if names.is_empty() {
+        return String::from("No corners move.");
+    }
+    format!("Corners moved: {}.", names.join(", "))
+}
+
+// Makes a step from 'moves' on the normalized 'cube', which it then applies, and adds the corners that move to the
+// explanation. 'tokens' are the moves as they are written for the cube as it is held.
+fn explained_step(cube: &mut Cube, name: String, explanation: &str, moves: Vec<Move>, tokens: Vec<Token>) -> Step {
+    let explanation = format!("{} {}", explanation, moved_corners_sentence(cube, &moves));
+    *cube = cube.sequence_moves(moves.iter());
+    Step {name, explanation, moves: cube::Macro::from(tokens)}
+}
+
+/// Solves the cube with the beginner's layer-by-layer method. The cube is first turned to hold the corner where
+/// the left, down and back sides meet at the back right of the down side, with its left sticker down. The first
+/// layer is built around it on the down side one corner at a time, by turning the cube to hold each corner's place
+/// at the front right and inserting the corner from above it. Then the last layer is oriented by repeating the
+/// Sune, and permuted with the T or Y permutation. Each step explains what it achieves, and which corners it moves.
+pub fn beginner_solution(cube: &Cube) -> Vec<Step> {
+    let normalizing_rotations = cube.normalizing_rotations(0);
+    let mut cube = cube.rotate_all(normalizing_rotations.iter());
+    let mut holding = Holding {rotations: vec![BEGINNER_ROTATION]};
+    let mut steps = Vec::new();
+
+    let rotations: Vec<cube::Rotation> =
+        normalizing_rotations.into_iter().chain(Some(BEGINNER_ROTATION)).collect();
+    let rotation_tokens: Vec<Token> = shortest_rotations(&rotations).into_iter().map(Token::Rotation).collect();
+    let held = format!("the {} corner at the back right of the down side, with its left sticker down", corner_name(0));
+    let explanation = {
+        if rotation_tokens.is_empty() {
+            format!("The cube is already held with {}.", held)
+        } else {
+            format!("Turns the whole cube to hold it with {}.", held)
+        }
+    };
+    steps.push(Step {
+        name: String::from("Holding"),
+        explanation: format!("{} The first layer is built around this corner on the down side, and the last layer \
+                              is finished on the up side.", explanation),
+        moves: cube::Macro::from(rotation_tokens),
+    });
+
+    let mut placed_position_idxs = vec![FIRST_LAYER_POSITIONS[0]];
+    for &position_idx in FIRST_LAYER_POSITIONS[1..].iter() {
+        placed_position_idxs.push(position_idx);
+        steps.push(first_layer_step(&mut cube, &mut holding, &placed_position_idxs));
+    }
+
+    let explanation = format!("Turns the up side to the angle where the Sune ({}) twists its corners towards facing \
+                               up, and performs it. The first layer is left as it was.", SUNE);
+    let setups = sune_setups(&cube, &holding);
+    if setups.is_empty() {
+        let name = String::from("Last layer orientation (all corners oriented)");
+        steps.push(explained_step(&mut cube, name, "The up side is already one colour.", vec![], vec![]));
+    }
+    for setup_moves in setups {
+        let name = {
+            match oriented_last_layer_corner_count(&cube) {
+                0 => "Last layer orientation (no corners oriented)",
+                1 => "Last layer orientation (one corner oriented)",
+                2 => "Last layer orientation (two corners oriented)",
+                _ => unreachable!("the last layer has no corners left to orient"),
+            }
+        };
+        let moves: Vec<Move> = setup_moves.into_iter().chain(holding.moves(SUNE)).collect();
+        let tokens = holding.tokens(&moves);
+        steps.push(explained_step(&mut cube, String::from(name), &explanation, moves, tokens));
+    }
+
+    let permutation_step = last_layer_permutation_step(&mut cube, &holding);
+    assert!(cube.is_solved());
+    steps.push(permutation_step);
+    steps
+}
+
+// Returns the step that puts the corner that belongs at 'position_idx', the last of 'placed_position_idxs', into
+// the first layer without moving the others. A corner that is in the first layer, but not in place, is first taken
+// out to the up side. The corner is then turned above its place, and inserted by the case of where its left sticker
+// faces.
+fn first_layer_step(cube: &mut Cube, holding: &mut Holding, placed_position_idxs: &[u8]) -> Step {
+    let position_idx = *placed_position_idxs.last().unwrap();
+    let name = corner_name(position_idx);
+    let is_placed = |cube: &Cube| {
+        let (positions, orientations) = cube.positions_orientations();
+        placed_position_idxs.iter().all(|&idx| is_corner_solved(&positions, &orientations, idx))
+    };
+    if is_placed(cube) {
+        let explanation = format!("The {} corner is already in the first layer.", name);
+        return explained_step(cube, format!("First layer ({} corner)", name), &explanation, vec![], vec![]);
+    }
+
+    let mut moves: Vec<Move> = Vec::new();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut explanation = String::new();
+    let current_position_idx = corner_position_idx(cube, position_idx);
+    if FIRST_LAYER_POSITIONS.contains(&current_position_idx) {
+        tokens.extend(holding.turn_to(current_position_idx, INSERTION_POSITION_IDX));
+        let extraction = holding.moves(CORNER_EXTRACTION);
+        tokens.extend(holding.tokens(&extraction));
+        moves.extend(extraction);
+        explanation.push_str(&format!("Holds the {} corner at the front right of the down side, and takes it out to \
+                                       the up side with {}. ", name, CORNER_EXTRACTION));
+    }
+
+    tokens.extend(holding.turn_to(position_idx, INSERTION_POSITION_IDX));
+    let above_position_idx = holding.position_idx(ABOVE_INSERTION_POSITION_IDX);
+    let up_turn = ["", "U", "U2", "U'"].iter().map(|notation| holding.moves(notation)).find(|turn| {
+        let turned_cube = cube.sequence_moves(moves.iter()).sequence_moves(turn.iter());
+        corner_position_idx(&turned_cube, position_idx) == above_position_idx
+    }).unwrap();
+    tokens.extend(holding.tokens(&up_turn));
+    moves.extend(up_turn);
+
+    for &(facing, notation) in CORNER_INSERTIONS.iter() {
+        let insertion = holding.moves(notation);
+        if is_placed(&cube.sequence_moves(moves.iter()).sequence_moves(insertion.iter())) {
+            tokens.extend(holding.tokens(&insertion));
+            moves.extend(insertion);
+            explanation.push_str(&format!("Holds the corner's place at the front right of the down side, turns the up \
+                                           side until the corner is above it, and inserts it with {}, since its left \
+                                           sticker, which goes down, faces {}.", notation, facing));
+            let name = format!("First layer ({} corner, left sticker facing {})", name, facing);
+            return explained_step(cube, name, &explanation, moves, tokens);
+        }
+    }
+    unreachable!("no corner insertion places the {} corner", name);
+}
+
+// Returns the turns of the up side to make before each Sune, for the fewest Sunes that orient the last layer.
+fn sune_setups(cube: &Cube, holding: &Holding) -> Vec<Vec<Move>> {
+    let sune = holding.moves(SUNE);
+    let last_layer_turns = layer_turns(Side::R);
+    for sune_count in 0..4 {
+        // Every choice of a turn before each Sune, as the digits of 'choice_idx' in base 4.
+        for choice_idx in 0..(1usize << (2*sune_count)) {
+            let setups: Vec<Vec<Move>> =
+                (0..sune_count).map(|i| last_layer_turns[(choice_idx >> (2*i)) & 3].clone()).collect();
+            let moves: Vec<Move> = setups.iter().flat_map(|setup| setup.iter().chain(sune.iter()).cloned()).collect();
+            if oriented_last_layer_corner_count(&cube.sequence_moves(moves.iter())) == 4 {
+                return setups;
+            }
+        }
+    }
+    unreachable!("no sequence of up to 3 Sunes orients the last layer");
+}
+
+// Returns the step that solves the cube with the first layer solved and the last layer oriented, and applies it.
+// The case is recognised by the corners that are in place after turning the last layer: none swapped, two
+// neighbouring corners swapped, or two opposite corners swapped.
+fn last_layer_permutation_step(cube: &mut Cube, holding: &Holding) -> Step {
+    let mut swapped_position_idxs = None;
+    for turn_moves in layer_turns(Side::R).iter() {
+        let (positions, _) = cube.sequence_moves(turn_moves.iter()).positions_orientations();
+        let misplaced: Vec<u8> =
+            LAST_LAYER_POSITIONS.iter().cloned().filter(|&idx| positions[idx as usize] != idx).collect();
+        if misplaced.is_empty() || misplaced.len() == 2 {
+            swapped_position_idxs = Some(misplaced);
+            break;
+        }
+    }
+    let (name, explanation, algorithm) = {
+        match *swapped_position_idxs.unwrap().as_slice() {
+            [] => ("Last layer permutation (solved)", "Turns the up side into place.", vec![]),
+            [i, j] if (i ^ j).count_ones() == 1 => (
+                "Last layer permutation (adjacent corners swapped)",
+                "Swaps two neighbouring corners of the last layer with the T permutation, turning the up side \
+                 before and after so that the swap lines up. The first layer is left as it was.",
+                holding.moves(T_PERMUTATION),
+            ),
+            [_, _] => (
+                "Last layer permutation (diagonal corners swapped)",
+                "Swaps two opposite corners of the last layer with the Y permutation, turning the up side before \
+                 and after so that the swap lines up. The first layer is left as it was.",
+                holding.moves(Y_PERMUTATION),
+            ),
+            _ => unreachable!("the last layer has more than two corners out of place"),
+        }
+    };
+    for pre_moves in layer_turns(Side::R).iter() {
+        for post_moves in layer_turns(Side::R).iter() {
+            let moves: Vec<Move> = pre_moves.iter().chain(algorithm.iter()).chain(post_moves.iter()).cloned().collect();
+            if cube.sequence_moves(moves.iter()).is_solved() {
+                let tokens = holding.tokens(&moves);
+                return explained_step(cube, String::from(name), explanation, moves, tokens);
+            }
+        }
+    }
+    unreachable!("no turns of the up side line the permutation up with the first layer");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube_after(notation: &str) -> Cube {
+        Cube::solved().sequence_moves(parse_moves(notation).iter())
+    }
+
+    // The cube that 'algorithm' solves.
+    fn case_cube(algorithm: &str) -> Cube {
+        let inverse_moves: Vec<Move> = parse_moves(algorithm).iter().rev().map(|m| m.inverse()).collect();
+        Cube::solved().sequence_moves(inverse_moves.iter())
+    }
+
+    #[test]
+    fn algorithms_solve_their_cases() {
+        for &(name, algorithm) in OLL_ALGORITHMS.iter() {
+            let cube = case_cube(algorithm);
+            assert!(is_side_done(&cube, Side::D) && !is_side_done(&cube, Side::U), "{}", name);
+            assert_eq!(oll_step(&cube).name, format!("OLL ({})", name));
+        }
+        for &(top, bottom, algorithm) in PBL_ALGORITHMS.iter() {
+            let cube = case_cube(algorithm);
+            assert!(is_side_done(&cube, Side::D) && is_side_done(&cube, Side::U), "{}", algorithm);
+            assert_eq!((layer_permutation(&cube, Side::U), layer_permutation(&cube, Side::D)), (top, bottom));
+        }
+    }
+
+    #[test]
+    fn solves_with_ortega_steps() {
+        let scrambles = [
+            "", "R U R' U R U2 R'", "R2 F2 R2", "x R U F' D2", "F2 U' R U' F R2 U F' R", "L D' B2 F R' U2",
+        ];
+        for scramble in scrambles.iter() {
+            let cube = cube_after(scramble);
+            let steps = ortega_solution(&cube);
+            assert_eq!(steps.len(), 3);
+            assert_eq!(steps[0].name, "Face");
+            assert!(steps[1].name.starts_with("OLL (") && steps[2].name.starts_with("PBL ("));
+            let moves: Vec<Move> = steps.iter().flat_map(|step| step.moves.move_iter().cloned()).collect();
+            assert!(cube.sequence_moves(moves.iter()).is_solved(), "{}", scramble);
+        }
+        let steps = ortega_solution(&cube_after("R2 F2 R2"));
+        assert_eq!(steps[2].name, "PBL (diagonal swap on top and diagonal swap on the bottom)");
+        assert_eq!(ortega_solution(&cube_after("R U R' U R U2 R'"))[1].name, "OLL (Antisune)");
+    }
+
+    #[test]
+    fn solves_with_beginner_steps() {
+        assert_eq!(Move::R3.rotated(BEGINNER_ROTATION), Move::U3);
+        assert_eq!(Move::L3.rotated(BEGINNER_ROTATION), Move::D3);
+        assert_eq!(corner_name(6), "left-up-front");
+        let scrambles = ["", "R U R' U'", "F2 U' R U' F R2 U F' R", "L D' B2 F R' U2", "x y R2 U F"];
+        for scramble in scrambles.iter() {
+            let cube = cube_after(scramble);
+            let steps = beginner_solution(&cube);
+            let names: Vec<&str> = steps.iter().map(|step| step.name.as_str()).collect();
+            assert_eq!(names[0], "Holding");
+            assert!(names[1].starts_with("First layer (left-up-back corner"));
+            assert!(names[2].starts_with("First layer (left-down-front corner"));
+            assert!(names[3].starts_with("First layer (left-up-front corner"));
+            assert!(names[4..names.len() - 1].iter().all(|name| name.starts_with("Last layer orientation (")));
+            assert!(names.last().unwrap().starts_with("Last layer permutation ("));
+            let moves: Vec<Move> = steps.iter().flat_map(|step| step.moves.move_iter().cloned()).collect();
+            assert!(cube.sequence_moves(moves.iter()).is_solved(), "{}", scramble);
+
+            // Once held, the corner that the first layer is built around has its left sticker down, at the back
+            // right of the down side.
+            let held_cube = cube.sequence_moves(steps[0].moves.move_iter());
+            assert!(steps[0].moves.face_moves().is_empty());
+            assert_eq!(held_cube.sticker([1, -1, -1], Side::D), Side::L);
+            assert_eq!(held_cube.sticker([1, -1, -1], Side::R), Side::D);
+        }
+
+        let solved_steps = beginner_solution(&Cube::solved());
+        assert_eq!(solved_steps[0].moves.to_string(), "z'");
+        assert!(solved_steps[1..].iter().all(|step| step.moves.moves.is_empty()));
+        assert!(solved_steps[1..].iter().all(|step| step.explanation.ends_with("No corners move.")));
+        assert_eq!(solved_steps[5].name, "Last layer permutation (solved)");
+
+        // The cases that the algorithms solve, set up on the cube as it is held. The algorithms are written as
+        // they are known.
+        let held_case_steps = |algorithm: &str| {
+            let holding = Holding {rotations: vec![BEGINNER_ROTATION]};
+            let inverse_moves: Vec<Move> = holding.moves(algorithm).iter().rev().map(|m| m.inverse()).collect();
+            beginner_solution(&Cube::solved().sequence_moves(inverse_moves.iter()))
+        };
+        for &(facing, algorithm) in CORNER_INSERTIONS.iter() {
+            let steps = held_case_steps(algorithm);
+            let insertion_step = steps[1..].iter().find(|step| !step.moves.moves.is_empty()).unwrap();
+            assert!(insertion_step.name.ends_with(&format!("corner, left sticker facing {})", facing)));
+            assert_eq!(insertion_step.moves.to_string(), algorithm);
+        }
+        let steps = held_case_steps(SUNE);
+        assert_eq!(steps[4].name, "Last layer orientation (one corner oriented)");
+        assert_eq!(steps[4].moves.to_string(), SUNE);
+        assert_eq!(steps[5].name, "Last layer permutation (solved)");
+        let steps = held_case_steps(T_PERMUTATION);
+        assert_eq!(steps[4].name, "Last layer orientation (all corners oriented)");
+        assert_eq!(steps[5].name, "Last layer permutation (adjacent corners swapped)");
+        assert_eq!(steps[5].moves.to_string(), T_PERMUTATION);
+        assert_eq!(held_case_steps(Y_PERMUTATION)[5].name, "Last layer permutation (diagonal corners swapped)");
+
+        // A corner in the first layer, but twisted, is taken out first.
+        let steps = beginner_solution(&cube_after("R U R' U' R U R' U'"));
+        assert!(steps[1].explanation.starts_with("Holds the left-up-back corner at the front right of the down side, \
+                                                  and takes it out to the up side with R U R'."));
+        let moved_corners = steps[1].explanation.split("Corners moved: ").nth(1).unwrap();
+        assert!(moved_corners.contains("left-up-back") && !moved_corners.contains("left-down-back"));
+    }
+}
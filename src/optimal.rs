@@ -0,0 +1,760 @@
+use std;
+use std::collections::HashMap;
+use cube;
+use cube::Move;
+use costs;
+use coordinates;
+use coordinates::Coordinates;
+use distances;
+use permutations;
+use solver::positions;
+use solver::orientations;
+
+// A search state in the same representation as the macro solver uses:
+// positions are indexed by cubie, orientations are indexed by position.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    positions: [u8; 8],
+    orientations: [u8; 8],
+}
+
+impl State {
+    fn from_cube(cube: &cube::Cube) -> State {
+        let (positions, orientations) = cube.positions_orientations();
+        State {
+            positions: permutations::inverse(positions),
+            orientations,
+        }
+    }
+
+    fn apply(&self, m: &Move) -> State {
+        let mut s = *self;
+        positions::apply(&mut s.positions, m);
+        orientations::apply(&mut s.orientations, m);
+        s
+    }
+}
+
+/// A way of counting the length of a sequence of moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    // Every turn of a side counts as one move.
+    HalfTurn,
+    // Quarter turns count as one move, and half turns count as two moves.
+    QuarterTurn,
+}
+
+impl Metric {
+    pub fn move_length(&self, m: &Move) -> usize {
+        use self::Metric::*;
+        match *self {
+            HalfTurn => 1,
+            QuarterTurn => if ((*m as u8) % 3) == 1 {2} else {1},
+        }
+    }
+
+    pub fn length(&self, moves: &[Move]) -> usize {
+        moves.iter().map(|m| self.move_length(m)).sum()
+    }
+}
+
+/// An amount by which a side may be turned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Turn {
+    CounterClockwise,
+    Half,
+    Clockwise,
+}
+
+/// The moves that a restricted solution may use: the turns of each of 'sides' by each of 'turns'.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveSet {
+    pub sides: Vec<cube::Side>,
+    pub turns: Vec<Turn>,
+}
+
+impl MoveSet {
+    pub fn contains(&self, m: Move) -> bool {
+        let side = cube::Side::from((m as i32) / 3);
+        let turn = [Turn::CounterClockwise, Turn::Half, Turn::Clockwise][(m as usize) % 3];
+        self.sides.contains(&side) && self.turns.contains(&turn)
+    }
+
+    /// Returns the moves in the set, in the order of their indices.
+    pub fn moves(&self) -> Vec<Move> {
+        (0..18u8).map(Move::from).filter(|&m| self.contains(m)).collect()
+    }
+}
+
+/// Lists the moves in the set, such as "R R' U U'".
+impl std::fmt::Display for MoveSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", cube::Macro::from(self.moves()))
+    }
+}
+
+/// Returns a shortest sequence of moves (in the half-turn metric) that solves the cube up to a whole-cube
+/// rotation.
+pub fn solution(cube: &cube::Cube) -> Vec<Move>
+{
+    metric_solution(cube, Metric::HalfTurn)
+}
+
+/// Returns a sequence of moves that solves the cube up to a whole-cube rotation, and that is as short as
+/// possible in the given metric.
+pub fn metric_solution(cube: &cube::Cube, metric: Metric) -> Vec<Move>
+{
+    rotated_solution(cube, |positions, orientations| fixed_corner_solution(positions, orientations, metric))
+}
+
+/// Returns a sequence of moves that solves the cube up to a whole-cube rotation, and that is as short as
+/// possible in the metric of 'table', by looking up distances instead of searching. Fails if the entries of
+/// the table do not lead to the solved state.
+pub fn table_solution(cube: &cube::Cube, table: &distances::DistanceTable)
+    -> Result<Vec<Move>, distances::TableReadingError>
+{
+    let rotations = cube.normalizing_rotations(0);
+    let start = State::from_cube(&cube.rotate_all(rotations.iter()));
+    match table.fixed_corner_solution(start.positions, start.orientations)
+    {
+        Ok(rotated_moves) => Ok(unrotated_moves(&rotations, &rotated_moves)),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns an iterator over the sequences of moves that solve the cube up to a whole-cube rotation, and that are
+/// as short as possible in the metric of 'table'. At most 'limit' sequences are returned, if given.
+///
+/// Turning a side is the same as turning the opposite side in the same direction and rotating the whole cube, so
+/// sequences that only differ by such rotations, such as "R'" and "L'", are returned once. They are found with the
+/// corner where the left, down and back sides meet kept in place, and then turned back to the sides of the cube
+/// as it was given. There may still be many solutions: setting a limit is recommended.
+pub fn all_solutions<'a>(
+    cube: &cube::Cube,
+    table: &'a distances::DistanceTable,
+    limit: Option<usize>
+) -> Result<OptimalSolutions<'a>, distances::TableReadingError>
+{
+    let distance = {
+        match table_solution(cube, table)
+        {
+            Ok(moves) => table.metric().length(&moves),
+            Err(error) => return Err(error),
+        }
+    };
+    let rotations = cube.normalizing_rotations(0);
+    Ok(OptimalSolutions {
+        table,
+        tables: coordinates::MoveTables::get(),
+        distance,
+        remaining_count: limit,
+        path: vec![(Coordinates::from_cube(&cube.rotate_all(rotations.iter())), 0)],
+        moves: Vec::new(),
+        rotations,
+    })
+}
+
+/// An iterator over the shortest solutions of a cube, in lexicographic order of the moves with cubie 0 home. See
+/// 'all_solutions'.
+pub struct OptimalSolutions<'a> {
+    table: &'a distances::DistanceTable,
+    tables: &'static coordinates::MoveTables,
+    // The distance from the cube to the solved state.
+    distance: usize,
+    remaining_count: Option<usize>,
+    // The coordinates of the states reached by the moves so far, starting with the cube to solve with cubie 0 home,
+    // each with the index of the next move to try from it. In the quarter-turn metric, half turns are made as two
+    // quarter turns.
+    path: Vec<(Coordinates, u8)>,
+    moves: Vec<Move>,
+    // The rotations that bring cubie 0 of the cube to solve home.
+    rotations: Vec<cube::Rotation>,
+}
+
+impl<'a> OptimalSolutions<'a> {
+    // Whether 'm' may follow the moves so far. Only the right, up and front sides are turned, which keep cubie 0
+    // home, and moves of the same side are merged.
+    fn may_follow(&self, m: Move) -> bool {
+        let metric = self.table.metric();
+        if metric == Metric::QuarterTurn && (m as u8) % 3 == 1 {
+            return false;
+        }
+        let side_idx = (m as u8) / 3;
+        if side_idx & 1 == 0 {
+            return false;
+        }
+        match self.moves.last() {
+            None => true,
+            Some(&previous) => {
+                let previous_side_idx = (previous as u8) / 3;
+                if previous_side_idx == side_idx {
+                    // In the quarter-turn metric, a half turn is only made as two clockwise quarter turns.
+                    let before_previous = self.moves.len().checked_sub(2).map(|idx| self.moves[idx]);
+                    return metric == Metric::QuarterTurn && m == previous && (m as u8) % 3 == 2 &&
+                        before_previous.is_none_or(|before| (before as u8) / 3 != side_idx);
+                }
+                true
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for OptimalSolutions<'a> {
+    type Item = cube::Macro;
+
+    fn next(&mut self) -> Option<cube::Macro> {
+        if self.remaining_count == Some(0) {
+            return None;
+        }
+        if self.distance == 0 && !self.path.is_empty() {
+            // The cube is solved already, and the only solution is to do nothing.
+            self.path.clear();
+            self.remaining_count = self.remaining_count.map(|count| count - 1);
+            return Some(cube::Macro::identity());
+        }
+        // A depth-first search, which only follows moves that bring the cube one move closer to solved.
+        loop {
+            let (coordinates, move_idx) = {
+                match self.path.last_mut() {
+                    None => return None,
+                    Some(&mut (coordinates, ref mut move_idx)) => {
+                        *move_idx += 1;
+                        (coordinates, *move_idx - 1)
+                    },
+                }
+            };
+            if move_idx >= 18 {
+                self.path.pop();
+                self.moves.pop();
+                continue;
+            }
+            let m = Move::from(move_idx);
+            if !self.may_follow(m) {
+                continue;
+            }
+            let next_coordinates = coordinates.apply(m, self.tables);
+            let next_distance = self.distance - self.path.len();
+            if self.table.coordinates_entry(next_coordinates) != (next_distance % 3) as u8 {
+                continue;
+            }
+            if next_distance == 0 {
+                let mut solution = self.moves.clone();
+                solution.push(m);
+                self.remaining_count = self.remaining_count.map(|count| count - 1);
+                return Some(cube::Macro::from(unrotated_moves(&self.rotations, &merge_quarter_turns(solution))));
+            }
+            self.path.push((next_coordinates, 0));
+            self.moves.push(m);
+        }
+    }
+}
+
+/// Returns a sequence of moves that turns 'start' into 'target', up to a whole-cube rotation, and that is as
+/// short as possible in the given metric. Solving a cube is the special case where 'target' is solved.
+pub fn transition(start: &cube::Cube, target: &cube::Cube, metric: Metric) -> Vec<Move>
+{
+    // Rotating the target to bring cubie 0 home, like the start, leaves only the right, up and front sides to
+    // turn.
+    let goal = State::from_cube(&target.normalized(0));
+    rotated_solution(start, |positions, orientations| {
+        let start = State {positions, orientations};
+        fixed_corner_transition(start, goal, metric)
+    })
+}
+
+/// Returns a sequence of moves from 'move_set' that solves the cube up to a whole-cube rotation, and that is as
+/// short as possible in the given metric, or None if the cube can not be solved with those moves.
+pub fn restricted_solution(cube: &cube::Cube, move_set: &MoveSet, metric: Metric) -> Option<Vec<Move>>
+{
+    let half_turns_allowed = move_set.turns.contains(&Turn::Half);
+    let quarter_turns_allowed = move_set.turns.iter().any(|&turn| turn != Turn::Half);
+    // In the quarter-turn metric, half turns are found as two quarter turns, if those are allowed. Otherwise,
+    // every move has the same length, so the search finds the shortest solution either way.
+    let generators: Vec<Move> = move_set.moves().into_iter()
+        .filter(|&m| metric == Metric::HalfTurn || !quarter_turns_allowed || (m as u8) % 3 != 1)
+        .collect();
+
+    // The sides that may be turned do not necessarily leave any cubie in place, so every rotation of the solved
+    // cube is a goal.
+    let goals: Vec<State> = solved_rotations().iter().map(State::from_cube).collect();
+    let moves = search(State::from_cube(cube), &goals, &generators)?;
+    // Consecutive quarter turns are only merged when half turns are allowed.
+    if half_turns_allowed {
+        return Some(merge_quarter_turns(moves));
+    }
+    Some(moves)
+}
+
+// Returns the 24 rotations of the solved cube. Each of them is one of the 9 rotations, or two of them in a row.
+fn solved_rotations() -> Vec<cube::Cube>
+{
+    let mut rotated_cubes: Vec<cube::Cube> = Vec::new();
+    let solved_cube = cube::Cube::solved();
+    for first_idx in 0..10u8 {
+        for second_idx in 0..10u8 {
+            let mut rotated_cube = solved_cube.clone();
+            for &idx in [first_idx, second_idx].iter().filter(|&&idx| idx < 9) {
+                rotated_cube = rotated_cube.rotate(cube::Rotation::from(idx));
+            }
+            if !rotated_cubes.contains(&rotated_cube) {
+                rotated_cubes.push(rotated_cube);
+            }
+        }
+    }
+    assert_eq!(rotated_cubes.len(), 24);
+    rotated_cubes
+}
+
+// How a cube is turned relative to the same cube with cubie 0 home, as the move on the cube with cubie 0 home that
+// each move makes, indexed by move.
+type Frame = [Move; 18];
+
+// A cube reached by the search for the cheapest solution.
+struct ReachedCube {
+    // The coordinates of the cube with cubie 0 home.
+    coordinates: Coordinates,
+    // The index of the cube's frame. Cubes that only differ by a whole-cube rotation are told apart, since moves
+    // of different sides may cost differently.
+    frame_idx: usize,
+    // The distance to the solved state, in the metric of the distance table.
+    distance: usize,
+    // The lowest known cost of reaching the cube.
+    cost: u32,
+    // The index of the cube before this one on the cheapest known way, and the move from there.
+    previous: Option<(usize, Move)>,
+}
+
+// Returns the move of the side opposite to 'm', a move of the left, down or back side, that turns the cube the
+// same way once followed by the rotation that is also returned.
+fn opposite_move_and_rotation(m: Move) -> (Move, cube::Rotation) {
+    let r = (0..9).map(cube::Rotation::from).find(|r| r.moves()[0] == m).unwrap();
+    // The two moves of a rotation commute.
+    (r.moves()[1].inverse(), r)
+}
+
+/// Returns a sequence of moves that solves the cube up to a whole-cube rotation, with the lowest total cost.
+/// The distances in 'table', of either metric, are only used to speed up the search. Fails if the entries of
+/// the table do not lead to the solved state.
+pub fn cheapest_solution(cube: &cube::Cube, costs: &costs::MoveCosts, table: &distances::DistanceTable)
+    -> Result<Vec<Move>, distances::TableReadingError>
+{
+    // An A* search. Every move costs at least the minimum cost, and changes the distance by at most one, so
+    // the distance times the minimum cost never overestimates the remaining cost, nor drops by more than the
+    // cost of a move. A half turn is two moves in the quarter-turn metric.
+    let lower_bound = |distance: usize| {
+        let half_turn_distance = if table.metric() == Metric::QuarterTurn {distance.div_ceil(2)} else {distance};
+        half_turn_distance as u32 * costs.min_cost()
+    };
+    // The distance after a move that changes it by at most one, from the entries before and after it, which are
+    // the distances modulo 3.
+    let next_distance = |distance: usize, coordinates: Coordinates, next_coordinates: Coordinates| {
+        let entry_change = (table.coordinates_entry(next_coordinates) + 3 - table.coordinates_entry(coordinates)) % 3;
+        match entry_change {
+            0 => distance,
+            1 => distance + 1,
+            _ => distance.saturating_sub(1),
+        }
+    };
+    let tables = coordinates::MoveTables::get();
+
+    // The cube is solved with cubie 0 home. A move of the left, down or back side moves cubie 0, so it is made as
+    // the same move of the opposite side, and the frame is rotated instead.
+    let rotations = cube.normalizing_rotations(0);
+    let start_frame: Frame = {
+        let mut frame = [Move::L1; 18];
+        for move_idx in 0..18u8 {
+            frame[move_idx as usize] = rotations.iter().fold(Move::from(move_idx), |m, &r| m.rotated(r));
+        }
+        frame
+    };
+    let mut frames: Vec<Frame> = vec![start_frame];
+    let start_distance = table.distance(cube)?;
+    let start = ReachedCube {
+        coordinates: Coordinates::from_cube(&cube.rotate_all(rotations.iter())),
+        frame_idx: 0,
+        distance: start_distance,
+        cost: 0,
+        previous: None,
+    };
+    let mut cube_idxs: HashMap<(Coordinates, usize), usize> = HashMap::new();
+    cube_idxs.insert((start.coordinates, start.frame_idx), 0);
+    let mut reached_cubes = vec![start];
+    // Ordered by the lowest estimated total cost first.
+    let mut queue = std::collections::BinaryHeap::new();
+    queue.push(std::cmp::Reverse((lower_bound(start_distance), 0, 0)));
+
+    let solved_idx = loop {
+        let std::cmp::Reverse((_, cost, idx)) = queue.pop().unwrap();
+        if cost > reached_cubes[idx].cost {
+            // The cube has been reached more cheaply since this entry was queued.
+            continue;
+        }
+        let (coordinates, frame_idx, distance) =
+            (reached_cubes[idx].coordinates, reached_cubes[idx].frame_idx, reached_cubes[idx].distance);
+        if coordinates == (Coordinates {permutation: 0, orientation: 0}) {
+            break idx;
+        }
+        for move_idx in 0..18u8 {
+            let m = Move::from(move_idx);
+            let frame_move = frames[frame_idx][move_idx as usize];
+            let (fixed_corner_move, next_frame_idx) = {
+                if ((frame_move as u8) / 3) & 1 == 1 {
+                    (frame_move, frame_idx)
+                } else {
+                    let (opposite_move, r) = opposite_move_and_rotation(frame_move);
+                    let mut next_frame = frames[frame_idx];
+                    for frame_move in next_frame.iter_mut() {
+                        *frame_move = frame_move.rotated(r.inverse());
+                    }
+                    let next_frame_idx = {
+                        match frames.iter().position(|frame| *frame == next_frame)
+                        {
+                            Some(next_frame_idx) => next_frame_idx,
+                            None => {
+                                frames.push(next_frame);
+                                frames.len() - 1
+                            },
+                        }
+                    };
+                    (opposite_move, next_frame_idx)
+                }
+            };
+            let next_coordinates = coordinates.apply(fixed_corner_move, tables);
+            let next_distance = {
+                if table.metric() == Metric::QuarterTurn && (fixed_corner_move as u8) % 3 == 1 {
+                    // Two quarter turns, each changing the distance by at most one.
+                    let quarter_turn = Move::from((fixed_corner_move as u8) + 1);
+                    let halfway_coordinates = coordinates.apply(quarter_turn, tables);
+                    let halfway_distance = next_distance(distance, coordinates, halfway_coordinates);
+                    next_distance(halfway_distance, halfway_coordinates, next_coordinates)
+                } else {
+                    next_distance(distance, coordinates, next_coordinates)
+                }
+            };
+            let next_cost = cost + costs.cost(m);
+            let next_idx = {
+                match cube_idxs.get(&(next_coordinates, next_frame_idx)) {
+                    Some(&next_idx) if reached_cubes[next_idx].cost <= next_cost => continue,
+                    Some(&next_idx) => {
+                        reached_cubes[next_idx].cost = next_cost;
+                        reached_cubes[next_idx].previous = Some((idx, m));
+                        next_idx
+                    },
+                    None => {
+                        cube_idxs.insert((next_coordinates, next_frame_idx), reached_cubes.len());
+                        reached_cubes.push(ReachedCube {
+                            coordinates: next_coordinates,
+                            frame_idx: next_frame_idx,
+                            distance: next_distance,
+                            cost: next_cost,
+                            previous: Some((idx, m)),
+                        });
+                        reached_cubes.len() - 1
+                    },
+                }
+            };
+            let estimated_cost = next_cost + lower_bound(reached_cubes[next_idx].distance);
+            queue.push(std::cmp::Reverse((estimated_cost, next_cost, next_idx)));
+        }
+    };
+
+    let mut moves = Vec::new();
+    let mut idx = solved_idx;
+    while let Some((previous_idx, m)) = reached_cubes[idx].previous {
+        moves.push(m);
+        idx = previous_idx;
+    }
+    moves.reverse();
+    Ok(moves)
+}
+
+// Solves the cube with 'solve_fixed_corner', which takes positions (indexed by cubie) and orientations
+// (indexed by position) with cubie 0 home.
+fn rotated_solution<F>(cube: &cube::Cube, solve_fixed_corner: F) -> Vec<Move>
+where
+    F: FnOnce([u8; 8], [u8; 8]) -> Vec<Move>
+{
+    // A 2x2 cube has no centers, so we may rotate the whole cube to bring cubie 0 home, and solve the rest of
+    // the cube relative to it, using only the three sides that do not contain it.
+    let rotations = cube.normalizing_rotations(0);
+    let start = State::from_cube(&cube.rotate_all(rotations.iter()));
+    let rotated_moves = solve_fixed_corner(start.positions, start.orientations);
+    unrotated_moves(&rotations, &rotated_moves)
+}
+
+// Returns the moves that turn the same sides of the cube as it was before 'rotations' as 'rotated_moves' turn
+// after them.
+fn unrotated_moves(rotations: &[cube::Rotation], rotated_moves: &[Move]) -> Vec<Move>
+{
+    rotated_moves.iter().map(|m| rotations.iter().rev().fold(*m, |m, r| m.rotated(r.inverse()))).collect()
+}
+
+// Returns a shortest solution (in the given metric) of the state with cubie 0 at position 0 in orientation 0,
+// and the other cubies at 'positions' (indexed by cubie) in 'orientations' (indexed by position).
+pub(crate) fn fixed_corner_solution(positions: [u8; 8], orientations: [u8; 8], metric: Metric) -> Vec<Move>
+{
+    let start = State {positions, orientations};
+    let goal = State {positions: permutations::IDENTITY, orientations: [0; 8]};
+    fixed_corner_transition(start, goal, metric)
+}
+
+// Returns a shortest sequence of moves (in the given metric) from 'start' to 'goal', which both have cubie 0 at
+// position 0 in orientation 0.
+fn fixed_corner_transition(start: State, goal: State, metric: Metric) -> Vec<Move>
+{
+    assert!(start.positions[0] == 0 && start.orientations[0] == 0);
+    assert!(goal.positions[0] == 0 && goal.orientations[0] == 0);
+
+    // Position 0 is where the left, down and back sides meet, so only the right, up and front sides are turned.
+    let moves = {
+        match search(start, &[goal], &distances::generators(metric)) {
+            Some(moves) => moves,
+            None => panic!(),
+        }
+    };
+    merge_quarter_turns(moves)
+}
+
+// Merges consecutive identical quarter turns into half turns.
+pub(crate) fn merge_quarter_turns(moves: Vec<Move>) -> Vec<Move>
+{
+    let mut merged_moves: Vec<Move> = Vec::new();
+    for m in moves {
+        match merged_moves.last() {
+            Some(&previous) if previous == m => {
+                let idx = m as u8;
+                *merged_moves.last_mut().unwrap() = Move::from(3*(idx/3) + 1);
+            },
+            _ => merged_moves.push(m),
+        }
+    }
+    merged_moves
+}
+
+// Bidirectional breadth-first search from 'start' to the nearest of 'goals', using 'generators' as the only
+// moves. Returns None if none of 'goals' can be reached from 'start'.
+fn search(start: State, goals: &[State], generators: &[Move]) -> Option<Vec<Move>>
+{
+    if goals.contains(&start) {
+        return Some(vec![]);
+    }
+
+    // Each visited state maps to its neighbour one step closer to the root of its search, along with the
+    // move that leads from the visited state to that neighbour, in the direction of the search.
+    let mut forward: HashMap<State, Option<(State, Move)>> = HashMap::new();
+    let mut backward: HashMap<State, Option<(State, Move)>> = HashMap::new();
+    forward.insert(start, None);
+    for &goal in goals.iter() {
+        backward.insert(goal, None);
+    }
+    let mut forward_frontier = vec![start];
+    let mut backward_frontier = goals.to_vec();
+
+    let meeting_state = 'search: loop {
+        if forward_frontier.is_empty() || backward_frontier.is_empty() {
+            return None;
+        }
+        if forward_frontier.len() <= backward_frontier.len() {
+            let mut next_frontier = Vec::new();
+            for state in forward_frontier.iter() {
+                for m in generators {
+                    let next_state = state.apply(m);
+                    if forward.contains_key(&next_state) {
+                        continue;
+                    }
+                    forward.insert(next_state, Some((*state, *m)));
+                    if backward.contains_key(&next_state) {
+                        break 'search next_state;
+                    }
+                    next_frontier.push(next_state);
+                }
+            }
+            forward_frontier = next_frontier;
+        } else {
+            let mut next_frontier = Vec::new();
+            for state in backward_frontier.iter() {
+                for m in generators {
+                    let next_state = state.apply(&m.inverse());
+                    if backward.contains_key(&next_state) {
+                        continue;
+                    }
+                    backward.insert(next_state, Some((*state, *m)));
+                    if forward.contains_key(&next_state) {
+                        break 'search next_state;
+                    }
+                    next_frontier.push(next_state);
+                }
+            }
+            backward_frontier = next_frontier;
+        }
+    };
+
+    let mut moves = Vec::new();
+    {
+        let mut state = meeting_state;
+        while let Some(&Some((previous_state, m))) = forward.get(&state) {
+            moves.push(m);
+            state = previous_state;
+        }
+        moves.reverse();
+    }
+    {
+        let mut state = meeting_state;
+        while let Some(&Some((next_state, m))) = backward.get(&state) {
+            moves.push(m);
+            state = next_state;
+        }
+    }
+    Some(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_rotated_cubes() {
+        let solved_cube = cube::Cube::solved();
+        for scramble in ["R U F' x", "y L2 D B' R z' U", "L D' B2 F R' U2 x2 D"].iter() {
+            let scramble: cube::Macro = scramble.parse().unwrap();
+            let cube = solved_cube.sequence_moves(scramble.move_iter()).rotate(cube::Rotation::Y1);
+            for &metric in [Metric::HalfTurn, Metric::QuarterTurn].iter() {
+                let moves = metric_solution(&cube, metric);
+                assert!(metric.length(&moves) <= metric.length(&scramble.moves));
+                assert!(cube.sequence_moves(moves.iter()).is_solved());
+            }
+        }
+    }
+
+    #[test]
+    fn finds_all_solutions() {
+        let solved_cube = cube::Cube::solved();
+        let all_solutions_of = |scramble: &str, table: &distances::DistanceTable, limit: Option<usize>| {
+            let cube = solved_cube.sequence_moves(scramble.parse::<cube::Macro>().unwrap().move_iter());
+            all_solutions(&cube, table, limit).unwrap().map(|solution| solution.to_string()).collect::<Vec<String>>()
+        };
+        let half_turn_table = distances::DistanceTable::generate(Metric::HalfTurn);
+        assert_eq!(all_solutions_of("", &half_turn_table, None), vec![""]);
+        // "L'" solves it too, but only by rotating the whole cube differently.
+        assert_eq!(all_solutions_of("R", &half_turn_table, None), vec!["R'"]);
+        // The corner where the left, down and back sides meet is kept in place, so "L" is undone from the right.
+        assert_eq!(all_solutions_of("L", &half_turn_table, None), vec!["R'"]);
+        assert_eq!(all_solutions_of("R U", &half_turn_table, None), vec!["U' R'"]);
+        assert_eq!(all_solutions_of("R U F", &half_turn_table, None), vec!["F' U' R'"]);
+        let quarter_turn_table = distances::DistanceTable::generate(Metric::QuarterTurn);
+        // In the quarter-turn metric, "U' L R" is as short, but it is "U' R2" with the whole cube rotated.
+        assert_eq!(all_solutions_of("R2 U", &quarter_turn_table, None), vec!["U' R2"]);
+
+        assert_eq!(all_solutions_of("R2 F2 R2", &half_turn_table, None), vec!["R2 F2 R2", "F2 R2 F2"]);
+
+        let scramble: cube::Macro = "F R U' R' U' R U R' F' R U R' U' R' F R F'".parse().unwrap();
+        let cube = solved_cube.sequence_moves(scramble.move_iter());
+        let length = solution(&cube).len();
+        assert_eq!(all_solutions(&cube, &half_turn_table, None).unwrap().count(), 24);
+        let solutions: Vec<cube::Macro> = all_solutions(&cube, &half_turn_table, Some(10)).unwrap().collect();
+        assert_eq!(solutions.len(), 10);
+        let notations: std::collections::HashSet<String> = solutions.iter().map(|s| s.to_string()).collect();
+        assert_eq!(notations.len(), solutions.len());
+        for solution in solutions.iter() {
+            assert_eq!(solution.moves.len(), length);
+            assert_eq!(solution.simplified().to_string(), solution.to_string());
+            assert!(cube.sequence_moves(solution.move_iter()).is_solved());
+        }
+    }
+
+    #[test]
+    fn solves_with_restricted_moves() {
+        let solved_cube = cube::Cube::solved();
+        let restricted_solution_of = |scramble: &str, sides: &[cube::Side], turns: &[Turn], metric: Metric| {
+            let cube = solved_cube.sequence_moves(scramble.parse::<cube::Macro>().unwrap().move_iter());
+            let move_set = MoveSet {sides: sides.to_vec(), turns: turns.to_vec()};
+            let optional_moves = restricted_solution(&cube, &move_set, metric);
+            if let Some(ref moves) = optional_moves {
+                assert!(moves.iter().all(|&m| move_set.contains(m)));
+                assert!(cube.sequence_moves(moves.iter()).is_solved());
+            }
+            optional_moves.map(|moves| cube::Macro::from(moves).to_string())
+        };
+        use cube::Side::*;
+        let all_turns = [Turn::CounterClockwise, Turn::Half, Turn::Clockwise];
+        let solution = restricted_solution_of("R U2 R' U' R U' R'", &[R, U], &all_turns, Metric::HalfTurn);
+        assert!(solution.unwrap().split(' ').count() <= 7);
+        assert_eq!(restricted_solution_of("R U F", &[R, U], &all_turns, Metric::HalfTurn), None);
+        let half_turn_solution = restricted_solution_of("R2 U2 F2", &[R, U, F], &[Turn::Half], Metric::QuarterTurn);
+        assert_eq!(half_turn_solution.unwrap(), "F2 U2 R2");
+        assert_eq!(restricted_solution_of("R", &[R], &[Turn::Clockwise], Metric::HalfTurn).unwrap(), "R R R");
+        assert_eq!(restricted_solution_of("R2 U", &[R, U], &all_turns, Metric::QuarterTurn).unwrap(), "U' R2");
+        // Turning the left side is the same as turning the right side and then rotating the whole cube.
+        assert_eq!(restricted_solution_of("L", &[R], &all_turns, Metric::HalfTurn).unwrap(), "R'");
+        assert_eq!(restricted_solution_of("", &[], &[], Metric::HalfTurn).unwrap(), "");
+        assert_eq!(restricted_solution_of("R", &[], &[], Metric::HalfTurn), None);
+    }
+
+    #[test]
+    fn finds_cheapest_solutions() {
+        let table = distances::DistanceTable::generate(Metric::QuarterTurn);
+        for scramble in ["R U F'", "F2 U' R U' F"].iter() {
+            let cube = cube::Cube::solved().sequence_moves(scramble.parse::<cube::Macro>().unwrap().move_iter());
+            let uniform_costs = costs::MoveCosts::uniform();
+            let moves = cheapest_solution(&cube, &uniform_costs, &table).unwrap();
+            assert!(cube.sequence_moves(moves.iter()).is_solved());
+            assert_eq!(moves.len(), solution(&cube).len());
+
+            // Turns of the front and back sides are slow, so a longer solution may be cheaper.
+            let costs = costs::MoveCosts::parse("F 3\nF' 3\nF2 3\nB 3\nB' 3\nB2 3").unwrap();
+            let moves = cheapest_solution(&cube, &costs, &table).unwrap();
+            assert!(cube.sequence_moves(moves.iter()).is_solved());
+            assert!(costs.total_cost(&moves) <= costs.total_cost(&solution(&cube)));
+            assert!(moves.len() >= solution(&cube).len());
+        }
+
+        // Turning the left side instead of the right side also rotates the cube, but it is cheaper here.
+        let cube = cube::Cube::solved().sequence_moves("R U".parse::<cube::Macro>().unwrap().move_iter());
+        let costs = costs::MoveCosts::parse("R' 5\nB' 5\nF' 5").unwrap();
+        assert_eq!(cube::Macro::from(cheapest_solution(&cube, &costs, &table).unwrap()).to_string(), "U' L'");
+    }
+
+    #[test]
+    fn finds_cheapest_solutions_with_skewed_costs() {
+        // Only the right and up sides are cheap, so the search goes through many more cubes than the distances
+        // suggest, but it still finishes: each cube is reached once per whole-cube rotation at most.
+        let table = distances::DistanceTable::generate(Metric::HalfTurn);
+        let mut costs_text = String::new();
+        for side in ["L", "D", "B", "F"].iter() {
+            for suffix in ["", "'", "2"].iter() {
+                costs_text.push_str(&format!("{}{} 100\n", side, suffix));
+            }
+        }
+        let costs = costs::MoveCosts::parse(&costs_text).unwrap();
+        let cube = cube::Cube::solved().sequence_moves("R U F".parse::<cube::Macro>().unwrap().move_iter());
+        let moves = cheapest_solution(&cube, &costs, &table).unwrap();
+        assert!(cube.sequence_moves(moves.iter()).is_solved());
+        assert_eq!(moves.iter().filter(|&&m| costs.cost(m) == 100).count(), 1);
+        assert_eq!(costs.total_cost(&moves), 102);
+    }
+
+    #[test]
+    fn finds_transitions() {
+        let solved_cube = cube::Cube::solved();
+        let cube_after = |notation: &str| {
+            solved_cube.sequence_moves(notation.parse::<cube::Macro>().unwrap().move_iter())
+        };
+        // The second target is a Sune, and the last pair only differs by a whole-cube rotation.
+        let pairs = [("R U F'", "R2 F2 U2"), ("x y R U' F2", "R U R' U R U2 R'"), ("", "L D"), ("F R", "F R z")];
+        for &(start_notation, target_notation) in pairs.iter() {
+            let start = cube_after(start_notation);
+            let target = cube_after(target_notation);
+            for &metric in [Metric::HalfTurn, Metric::QuarterTurn].iter() {
+                let moves = transition(&start, &target, metric);
+                let reached = start.sequence_moves(moves.iter());
+                assert!(reached.normalized(0) == target.normalized(0));
+                // Undoing the start and then doing the target also works, so the transition is no longer.
+                let undo_and_do: Vec<Move> = metric_solution(&start, metric).into_iter()
+                    .chain(target_notation.parse::<cube::Macro>().unwrap().moves).collect();
+                assert!(metric.length(&moves) <= metric.length(&undo_and_do));
+            }
+        }
+        assert_eq!(transition(&cube_after("F R"), &cube_after("F R z"), Metric::HalfTurn).len(), 0);
+        assert_eq!(transition(&cube_after("R U"), &cube_after("R U R"), Metric::HalfTurn).len(), 1);
+    }
+}
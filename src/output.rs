@@ -0,0 +1,457 @@
+use rubik2x2::cube;
+use rubik2x2::costs;
+use rubik2x2::methods;
+use rubik2x2::optimal;
+use rubik2x2::verification;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format
+{
+    // All moves on one line, separated by spaces, followed by the length of the solution in each metric.
+    Plain,
+    // One move per line, followed by the length of the solution in each metric.
+    Lines,
+    // A JSON object with the moves, the length of the solution in each metric, and the number of moves that
+    // simplifying the solution saved.
+    Json,
+    // Comma-separated values, with a header line for reports.
+    Csv,
+}
+
+// The number of states at each distance from the solved state, for one column of the depth report.
+pub struct DepthCounts
+{
+    pub metric: optimal::Metric,
+    // Whether only the states with a fixed corner are counted, turning only the right, up and front sides.
+    pub fixed_corner: bool,
+    pub counts: Vec<usize>,
+}
+
+impl DepthCounts
+{
+    fn title(&self) -> String
+    {
+        let states = if self.fixed_corner {"fixed corner"} else {"all states"};
+        format!("{}, {}", metric_key(self.metric).replace('_', "-"), states)
+    }
+
+    // The title as a single word, for the CSV header.
+    fn key(&self) -> String
+    {
+        let states = if self.fixed_corner {"fixed_corner"} else {"all_states"};
+        format!("{}_{}", metric_key(self.metric), states)
+    }
+
+    // The count at 'distance', which is 0 beyond the largest distance.
+    fn count(&self, distance: usize) -> usize
+    {
+        self.counts.get(distance).cloned().unwrap_or(0)
+    }
+}
+
+fn metric_key(metric: optimal::Metric) -> &'static str
+{
+    match metric
+    {
+        optimal::Metric::HalfTurn => "half_turn",
+        optimal::Metric::QuarterTurn => "quarter_turn",
+    }
+}
+
+// Quotes 'text' as a JSON string, escaping the quotes, backslashes and control characters in it.
+fn json_string(text: &str) -> String
+{
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c
+        {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Quotes 'text' as a CSV field if it has commas, quotes or line breaks, doubling the quotes in it.
+fn csv_field(text: &str) -> String
+{
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+// Formats a solution with its length in each metric. The plain and lines formats give the length on a last line
+// of its own, after the moves.
+pub fn format_solution(moves: &[cube::Move], simplification_saved_moves_count: usize, format: Format) -> String
+{
+    let half_turn_length = optimal::Metric::HalfTurn.length(moves);
+    let quarter_turn_length = optimal::Metric::QuarterTurn.length(moves);
+    let length_line = format!("({} HTM, {} QTM)\n", half_turn_length, quarter_turn_length);
+    match format
+    {
+        Format::Plain => format!("{}\n{}", cube::Macro::from(moves), length_line),
+        Format::Lines =>
+        {
+            let mut text: String = moves.iter().map(|m| format!("{}\n", m)).collect();
+            text.push_str(&length_line);
+            text
+        },
+        Format::Csv =>
+            format!("moves,half_turn,quarter_turn\n{},{},{}\n",
+                    csv_field(&cube::Macro::from(moves).to_string()), half_turn_length, quarter_turn_length),
+        Format::Json =>
+        {
+            let quoted_moves: Vec<String> = moves.iter().map(|m| json_string(&m.to_string())).collect();
+            format!(
+                "{{\"moves\": [{}], \"length\": {{\"half_turn\": {}, \"quarter_turn\": {}}}, \
+                 \"simplification_saved\": {}}}\n",
+                quoted_moves.join(", "),
+                half_turn_length,
+                quarter_turn_length,
+                simplification_saved_moves_count
+            )
+        },
+    }
+}
+
+// Formats a solution in named steps. The plain and lines formats both give a line for each step, with its name
+// and its moves, followed by an indented line with its explanation.
+pub fn format_steps(steps: &[methods::Step], format: Format) -> String
+{
+    match format
+    {
+        Format::Plain | Format::Lines =>
+        {
+            steps.iter().map(|step| {
+                format!("{}: {}\n    {}\n", step.name, step.moves, step.explanation)
+            }).collect()
+        },
+        Format::Csv =>
+        {
+            let mut text = String::from("step,moves,explanation\n");
+            for step in steps.iter() {
+                text.push_str(&format!("{},{},{}\n", csv_field(&step.name), csv_field(&step.moves.to_string()),
+                                       csv_field(&step.explanation)));
+            }
+            text
+        },
+        Format::Json =>
+        {
+            // Rotations are listed with the moves, but don't count towards the length.
+            let moves: Vec<cube::Move> = steps.iter().flat_map(|step| step.moves.face_moves()).collect();
+            let objects: Vec<String> = steps.iter().map(|step| {
+                let quoted_moves: Vec<String> =
+                    step.moves.tokens().iter().map(|token| json_string(&token.to_string())).collect();
+                format!("{{\"name\": {}, \"explanation\": {}, \"moves\": [{}]}}",
+                        json_string(&step.name), json_string(&step.explanation), quoted_moves.join(", "))
+            }).collect();
+            format!(
+                "{{\"steps\": [{}], \"length\": {{\"half_turn\": {}, \"quarter_turn\": {}}}}}\n",
+                objects.join(", "),
+                optimal::Metric::HalfTurn.length(&moves),
+                optimal::Metric::QuarterTurn.length(&moves)
+            )
+        },
+    }
+}
+
+// Formats the cheapest solution with 'costs' next to the shortest solution in 'metric', with the length and
+// the cost of each. The plain and lines formats both give an aligned table.
+pub fn format_cost_comparison(
+    cheapest_moves: &[cube::Move],
+    shortest_moves: &[cube::Move],
+    costs: &costs::MoveCosts,
+    metric: optimal::Metric,
+    format: Format
+) -> String
+{
+    let rows = [("cheapest", cheapest_moves), ("shortest", shortest_moves)];
+    match format
+    {
+        Format::Plain | Format::Lines =>
+        {
+            let mut text = String::from("Solution  Length  Cost  Moves\n");
+            for &(name, moves) in rows.iter() {
+                text.push_str(&format!("{:<8}  {:>6}  {:>4}  {}\n",
+                                       name, metric.length(moves), costs.total_cost(moves), cube::Macro::from(moves)));
+            }
+            text
+        },
+        Format::Csv =>
+        {
+            let mut text = String::from("solution,length,cost,moves\n");
+            for &(name, moves) in rows.iter() {
+                text.push_str(&format!("{},{},{},{}\n", csv_field(name), metric.length(moves), costs.total_cost(moves),
+                                       csv_field(&cube::Macro::from(moves).to_string())));
+            }
+            text
+        },
+        Format::Json =>
+        {
+            let objects: Vec<String> = rows.iter().map(|&(name, moves)| {
+                let quoted_moves: Vec<String> = moves.iter().map(|m| json_string(&m.to_string())).collect();
+                format!("{}: {{\"moves\": [{}], \"length\": {}, \"cost\": {}}}",
+                        json_string(name), quoted_moves.join(", "), metric.length(moves), costs.total_cost(moves))
+            }).collect();
+            format!("{{\"metric\": {}, {}}}\n", json_string(metric_key(metric)), objects.join(", "))
+        },
+    }
+}
+
+pub fn format_verification_report(report: &verification::Report) -> String
+{
+    let mut text = format!(
+        "States: {}\nMaximum solution length: {}\nMean solution length: {:.2}\nSolution lengths:\n",
+        report.state_count,
+        report.max_length(),
+        report.mean_length()
+    );
+    for (length, &count) in report.length_histogram.iter().enumerate() {
+        if count > 0 {
+            text.push_str(&format!("{:>5} {:>12}\n", length, count));
+        }
+    }
+    text
+}
+
+// Formats the depth report with one column per entry of 'columns'. The plain and lines formats both give an
+// aligned table.
+pub fn format_depth_counts(columns: &[DepthCounts], format: Format) -> String
+{
+    let row_count = columns.iter().map(|column| column.counts.len()).max().unwrap_or(0);
+    match format
+    {
+        Format::Plain | Format::Lines =>
+        {
+            let widths: Vec<usize> = columns.iter().map(|column| column.title().len().max(10)).collect();
+            let mut text = String::from("Distance");
+            for (column, &width) in columns.iter().zip(widths.iter()) {
+                text.push_str(&format!("  {:>width$}", column.title(), width = width));
+            }
+            text.push('\n');
+            for distance in 0..row_count {
+                text.push_str(&format!("{:>8}", distance));
+                for (column, &width) in columns.iter().zip(widths.iter()) {
+                    // Distances beyond the largest one are left blank, so that the largest one stands out.
+                    let count =
+                        if distance < column.counts.len() {column.count(distance).to_string()} else {String::new()};
+                    text.push_str(&format!("  {:>width$}", count, width = width));
+                }
+                text.push('\n');
+            }
+            text.push_str(&format!("{:>8}", "Total"));
+            for (column, &width) in columns.iter().zip(widths.iter()) {
+                text.push_str(&format!("  {:>width$}", column.counts.iter().sum::<usize>(), width = width));
+            }
+            text.push('\n');
+            text
+        },
+        Format::Csv =>
+        {
+            let keys: Vec<String> = columns.iter().map(|column| column.key()).collect();
+            let mut text = format!("distance,{}\n", keys.join(","));
+            for distance in 0..row_count {
+                let counts: Vec<String> = columns.iter().map(|column| column.count(distance).to_string()).collect();
+                text.push_str(&format!("{},{}\n", distance, counts.join(",")));
+            }
+            text
+        },
+        Format::Json =>
+        {
+            let objects: Vec<String> = columns.iter().map(|column| {
+                let counts: Vec<String> = column.counts.iter().map(|count| count.to_string()).collect();
+                format!(
+                    "{{\"metric\": \"{}\", \"fixed_corner\": {}, \"counts\": [{}]}}",
+                    metric_key(column.metric),
+                    column.fixed_corner,
+                    counts.join(", ")
+                )
+            }).collect();
+            format!("[{}]\n", objects.join(", "))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn formats()
+    {
+        let moves = "R U2 F'".parse::<cube::Macro>().unwrap().moves;
+        assert_eq!(format_solution(&moves, 0, Format::Plain), "R U2 F'\n(3 HTM, 4 QTM)\n");
+        assert_eq!(format_solution(&moves, 0, Format::Lines), "R\nU2\nF'\n(3 HTM, 4 QTM)\n");
+        assert_eq!(format_solution(&moves, 0, Format::Csv), "moves,half_turn,quarter_turn\nR U2 F',3,4\n");
+        assert_eq!(
+            format_solution(&moves, 2, Format::Json),
+            "{\"moves\": [\"R\", \"U2\", \"F'\"], \"length\": {\"half_turn\": 3, \"quarter_turn\": 4}, \
+             \"simplification_saved\": 2}\n"
+        );
+        assert_eq!(
+            format_solution(&[], 0, Format::Json),
+            "{\"moves\": [], \"length\": {\"half_turn\": 0, \"quarter_turn\": 0}, \"simplification_saved\": 0}\n"
+        );
+    }
+
+    #[test]
+    fn formats_verification_report()
+    {
+        let report = verification::Report {state_count: 4, length_histogram: vec![1, 0, 3]};
+        assert_eq!(
+            format_verification_report(&report),
+            "States: 4\nMaximum solution length: 2\nMean solution length: 1.50\nSolution lengths:\n\
+             \x20   0            1\n\
+             \x20   2            3\n"
+        );
+    }
+
+    #[test]
+    fn formats_depth_counts()
+    {
+        let columns = [
+            DepthCounts {metric: optimal::Metric::HalfTurn, fixed_corner: true, counts: vec![1, 9, 2]},
+            DepthCounts {metric: optimal::Metric::QuarterTurn, fixed_corner: false, counts: vec![1, 12, 114, 3]},
+        ];
+        assert_eq!(
+            format_depth_counts(&columns, Format::Plain),
+            "Distance  half-turn, fixed corner  quarter-turn, all states\n\
+             \x20      0                        1                         1\n\
+             \x20      1                        9                        12\n\
+             \x20      2                        2                       114\n\
+             \x20      3                                                  3\n\
+             \x20  Total                       12                       130\n"
+        );
+        assert_eq!(
+            format_depth_counts(&columns, Format::Csv),
+            "distance,half_turn_fixed_corner,quarter_turn_all_states\n0,1,1\n1,9,12\n2,2,114\n3,0,3\n"
+        );
+        assert_eq!(
+            format_depth_counts(&columns, Format::Json),
+            "[{\"metric\": \"half_turn\", \"fixed_corner\": true, \"counts\": [1, 9, 2]}, \
+             {\"metric\": \"quarter_turn\", \"fixed_corner\": false, \"counts\": [1, 12, 114, 3]}]\n"
+        );
+    }
+
+    #[test]
+    fn formats_cost_comparison()
+    {
+        let costs = costs::MoveCosts::parse("F2 4").unwrap();
+        let cheapest_moves = "R2 L2".parse::<cube::Macro>().unwrap().moves;
+        let shortest_moves = "F2".parse::<cube::Macro>().unwrap().moves;
+        let format = |format| {
+            format_cost_comparison(&cheapest_moves, &shortest_moves, &costs, optimal::Metric::HalfTurn, format)
+        };
+        assert_eq!(
+            format(Format::Plain),
+            "Solution  Length  Cost  Moves\n\
+             cheapest       2     2  R2 L2\n\
+             shortest       1     4  F2\n"
+        );
+        assert_eq!(format(Format::Csv), "solution,length,cost,moves\ncheapest,2,2,R2 L2\nshortest,1,4,F2\n");
+        assert_eq!(
+            format(Format::Json),
+            "{\"metric\": \"half_turn\", \"cheapest\": {\"moves\": [\"R2\", \"L2\"], \"length\": 2, \"cost\": 2}, \
+             \"shortest\": {\"moves\": [\"F2\"], \"length\": 1, \"cost\": 4}}\n"
+        );
+    }
+
+    #[test]
+    fn formats_steps()
+    {
+        let steps = [
+            methods::Step {
+                name: String::from("Face"),
+                explanation: String::from("Makes one side a single colour, in any arrangement."),
+                moves: "y R U'".parse::<cube::Macro>().unwrap(),
+            },
+            methods::Step {
+                name: String::from("OLL (skip)"),
+                explanation: String::from("Nothing to do."),
+                moves: cube::Macro::identity(),
+            },
+        ];
+        assert_eq!(
+            format_steps(&steps, Format::Plain),
+            "Face: y R U'\n    Makes one side a single colour, in any arrangement.\nOLL (skip): \n    Nothing to do.\n"
+        );
+        assert_eq!(
+            format_steps(&steps, Format::Csv),
+            "step,moves,explanation\n\
+             Face,y R U',\"Makes one side a single colour, in any arrangement.\"\n\
+             OLL (skip),,Nothing to do.\n"
+        );
+        assert_eq!(
+            format_steps(&steps, Format::Json),
+            "{\"steps\": [{\"name\": \"Face\", \"explanation\": \"Makes one side a single colour, in any \
+             arrangement.\", \"moves\": [\"y\", \"R\", \"U'\"]}, {\"name\": \"OLL (skip)\", \"explanation\": \
+             \"Nothing to do.\", \"moves\": []}], \"length\": {\"half_turn\": 2, \"quarter_turn\": 2}}\n"
+        );
+    }
+
+    #[test]
+    fn escapes_quotes_and_commas()
+    {
+        let steps = [
+            methods::Step {
+                name: String::from("PBL (\"Y\", then \\ swap)"),
+                explanation: String::from("Swaps two corners,\nthen two more."),
+                moves: "R2".parse::<cube::Macro>().unwrap(),
+            },
+        ];
+        assert_eq!(
+            format_steps(&steps, Format::Csv),
+            "step,moves,explanation\n\"PBL (\"\"Y\"\", then \\ swap)\",R2,\"Swaps two corners,\nthen two more.\"\n"
+        );
+        assert_eq!(
+            format_steps(&steps, Format::Json),
+            "{\"steps\": [{\"name\": \"PBL (\\\"Y\\\", then \\\\ swap)\", \"explanation\": \"Swaps two corners,\\n\
+             then two more.\", \"moves\": [\"R2\"]}], \"length\": {\"half_turn\": 1, \"quarter_turn\": 2}}\n"
+        );
+        assert_eq!(json_string("tab\tend"), "\"tab\\u0009end\"");
+        assert_eq!(csv_field("R U R'"), "R U R'");
+    }
+
+    // Splits CSV text into the fields of each line, undoing the quoting. Quoted fields may not span lines.
+    fn csv_rows(text: &str) -> Vec<Vec<String>>
+    {
+        text.lines().map(|line| {
+            let mut fields = vec![String::new()];
+            let mut is_quoted = false;
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c
+                {
+                    '"' if is_quoted && chars.peek() == Some(&'"') => {
+                        chars.next();
+                        fields.last_mut().unwrap().push('"');
+                    },
+                    '"' => is_quoted = !is_quoted,
+                    ',' if !is_quoted => fields.push(String::new()),
+                    c => fields.last_mut().unwrap().push(c),
+                }
+            }
+            fields
+        }).collect()
+    }
+
+    #[test]
+    fn beginner_steps_keep_their_fields_in_csv()
+    {
+        let scramble = "R U2 F' R U' F2 U".parse::<cube::Macro>().unwrap();
+        let steps = methods::beginner_solution(&cube::Cube::solved().sequence_moves(scramble.move_iter()));
+        let rows = csv_rows(&format_steps(&steps, Format::Csv));
+        assert_eq!(rows.len(), steps.len() + 1);
+        for (row, step) in rows[1..].iter().zip(steps.iter()) {
+            assert_eq!(row, &[step.name.clone(), step.moves.to_string(), step.explanation.clone()]);
+        }
+        assert!(steps.iter().any(|step| step.explanation.contains(',')));
+    }
+}
@@ -17,7 +17,7 @@ pub fn inverse(p: [u8; 8]) -> [u8; 8] {
 }
 
 pub fn apply(a: &mut [u8; 8], p: &[u8; 8]) {
-    let orig = a.clone();
+    let orig = *a;
     for i in 0..8 {
         a[p[i] as usize] = orig[i];
     }
@@ -48,7 +48,7 @@ impl TranspositionIterator {
 impl Iterator for TranspositionIterator {
     type Item = (usize, usize);
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some((last, index)) = self.cycle_iterator.next() {
+        for (last, index) in self.cycle_iterator.by_ref() {
             let first = self.first;
             self.first = last;
             if first {
@@ -91,3 +91,46 @@ impl Iterator for CycleIterator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_undoes_permutation() {
+        let p = [3, 0, 7, 1, 6, 2, 5, 4];
+        let q = inverse(p);
+        assert_eq!(q, [1, 3, 5, 0, 7, 6, 4, 2]);
+        assert_eq!(inverse(q), p);
+        assert_eq!(inverse(IDENTITY), IDENTITY);
+        for i in 0..8 {
+            assert_eq!(q[p[i] as usize], i as u8);
+        }
+    }
+
+    #[test]
+    fn apply_moves_entries() {
+        // The entry at index i moves to index p[i].
+        let p = [3, 0, 7, 1, 6, 2, 5, 4];
+        let mut a = [10, 11, 12, 13, 14, 15, 16, 17];
+        apply(&mut a, &p);
+        assert_eq!(a, [11, 13, 15, 10, 17, 16, 14, 12]);
+        apply(&mut a, &inverse(p));
+        assert_eq!(a, [10, 11, 12, 13, 14, 15, 16, 17]);
+
+        let mut b = IDENTITY;
+        apply(&mut b, &p);
+        assert_eq!(b, inverse(p));
+    }
+
+    #[test]
+    fn transpositions_compose_to_permutation() {
+        let p = [3, 0, 7, 1, 6, 2, 5, 4];
+        // Swapping the entries of the identity in order sorts 'p', and so gives its inverse.
+        let mut a = IDENTITY;
+        for (i, j) in TranspositionIterator::new(p) {
+            a.swap(i, j);
+        }
+        assert_eq!(a, inverse(p));
+    }
+}
@@ -0,0 +1,48 @@
+use std;
+
+/// A small, seedable pseudo-random number generator (SplitMix64).
+///
+/// The same seed always gives the same sequence of numbers, which makes test runs reproducible. It is not
+/// suitable for cryptographic use.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {state: seed}
+    }
+
+    /// Seeds the generator from the system clock.
+    pub fn from_time() -> Random {
+        let duration = {
+            match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+                Ok(duration) => duration,
+                Err(error) => error.duration(),
+            }
+        };
+        Random::new(duration.as_secs() ^ ((duration.subsec_nanos() as u64) << 32))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed number in 0..bound.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0);
+        // Reject the top part of the range that would make smaller numbers more likely.
+        let limit = u64::MAX - (u64::MAX % bound);
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return x % bound;
+            }
+        }
+    }
+}
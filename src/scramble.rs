@@ -0,0 +1,93 @@
+use cube;
+use optimal;
+use random::Random;
+
+/// WCA regulations reject random states that can be solved in fewer moves than this.
+pub const WCA_MINIMUM_LENGTH: usize = 4;
+
+// Picks one of the 3,674,160 states with cubie 0 solved, uniformly at random.
+// Positions are indexed by cubie, and orientations are indexed by position.
+fn random_state(random: &mut Random) -> ([u8; 8], [u8; 8]) {
+    let mut positions = [0, 1, 2, 3, 4, 5, 6, 7];
+    for i in (2..8).rev() {
+        let j = 1 + random.below(i as u64) as usize;
+        positions.swap(i, j);
+    }
+    let mut orientations = [0u8; 8];
+    for orientation in orientations[1..7].iter_mut() {
+        *orientation = random.below(3) as u8;
+    }
+    // The twists of all corners must add up to a multiple of a whole turn.
+    let twist = orientations.iter().fold(0, |sum, o| (sum + o) % 3);
+    orientations[7] = (3 - twist) % 3;
+    (positions, orientations)
+}
+
+/// Returns a scramble that leads from the solved cube to a uniformly random state, among those that can not
+/// be solved in fewer than 'minimum_length' moves (in the half-turn metric). The scramble is as short as
+/// possible, and leaves the left-down-back corner in place.
+pub fn random_state_scramble(random: &mut Random, minimum_length: usize) -> cube::Macro {
+    loop {
+        let (positions, orientations) = random_state(random);
+        let solution = optimal::fixed_corner_solution(positions, orientations, optimal::Metric::HalfTurn);
+        if solution.len() >= minimum_length {
+            return cube::Macro::from(solution.iter().rev().map(|m| m.inverse()).collect::<Vec<cube::Move>>());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use permutations;
+
+    #[test]
+    fn scrambles_are_as_short_as_their_states_allow() {
+        let solved = cube::Cube::solved();
+        let mut random = Random::new(2018);
+        for _ in 0..10 {
+            let scramble = random_state_scramble(&mut random, WCA_MINIMUM_LENGTH);
+            assert!(scramble.moves.len() >= WCA_MINIMUM_LENGTH && scramble.moves.len() <= 11);
+            for pair in scramble.moves.windows(2) {
+                assert!((pair[0] as u8) / 3 != (pair[1] as u8) / 3, "{}", scramble);
+            }
+            // The state that the scramble leads to keeps the left-down-back corner in place, and can not be
+            // solved in fewer moves.
+            let (positions, orientations) = solved.sequence_moves(scramble.move_iter()).positions_orientations();
+            assert!(positions[0] == 0 && orientations[0] == 0);
+            let cubie_positions = permutations::inverse(positions);
+            let solution = optimal::fixed_corner_solution(cubie_positions, orientations, optimal::Metric::HalfTurn);
+            assert_eq!(solution.len(), scramble.moves.len());
+        }
+    }
+
+    #[test]
+    fn seeded_scrambles_are_reproducible() {
+        let first = random_state_scramble(&mut Random::new(7), WCA_MINIMUM_LENGTH);
+        let second = random_state_scramble(&mut Random::new(7), WCA_MINIMUM_LENGTH);
+        assert_eq!(first.to_string(), second.to_string());
+        // Changing the generator or the search changes the scrambles for every seed.
+        assert_eq!(first.to_string(), "U2 F' R' F R' F U F2");
+    }
+
+    #[test]
+    fn random_states_are_spread_out() {
+        // Every cubie should show up in every position, in every orientation.
+        let mut counts = [[[0; 3]; 8]; 8];
+        let mut random = Random::new(1);
+        for _ in 0..24000 {
+            let (positions, orientations) = random_state(&mut random);
+            for cubie_idx in 1..8 {
+                let position_idx = positions[cubie_idx] as usize;
+                counts[cubie_idx][position_idx][orientations[position_idx] as usize] += 1;
+            }
+        }
+        for cubie_counts in counts[1..].iter() {
+            for position_counts in cubie_counts[1..].iter() {
+                for &count in position_counts.iter() {
+                    assert!(count > 950 && count < 1350, "{}", count);
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use permutations;
 use cube;
 
+/// Returns a sequence of moves that solves the cube, by first moving every cubie to its position with corner
+/// swapping macros, and then twisting every cubie into its orientation with corner twisting macros.
 pub fn solution(cube: &cube::Cube) -> Vec<cube::Move>
 {
     let (initial_positions, initial_orientations) = cube.positions_orientations();
     
     let mp = positions::solve_macro(permutations::inverse(initial_positions));
-    let mut orientations = initial_orientations.clone();
+    let mut orientations = initial_orientations;
     for m in mp.move_iter() {
         orientations::apply(&mut orientations, m);
     }    
@@ -19,32 +21,7 @@ pub fn solution(cube: &cube::Cube) -> Vec<cube::Move>
     return solution_moves;
 }
 
-pub fn test()
-{
-    {
-        use solver::positions::*;
-        test_swaps();
-        test_solve([0, 1, 2, 3, 4, 5, 6, 7]);
-        test_solve([0, 1, 2, 4, 3, 5, 6, 7]);
-        test_solve([0, 7, 2, 4, 3, 5, 6, 1]);
-        test_solve([6, 7, 5, 2, 3, 4, 0, 1]);
-    }
-
-    {
-        use solver::orientations::*;
-        test_apply();
-        test_twists();
-        test_solve([0, 1, 2, 0, 1, 2, 0, 0]);
-        test_solve([1, 1, 1, 2, 2, 2, 1, 2]);
-        test_solve([1, 1, 1, 0, 2, 2, 0, 2]);
-        test_solve([0, 0, 0, 0, 0, 0, 0, 0]);
-        test_solve([2, 0, 0, 0, 0, 0, 0, 1]);
-        test_solve([2, 0, 0, 1, 1, 1, 0, 1]);
-        test_solve([2, 2, 2, 2, 2, 2, 2, 1]);
-    }    
-}
-
-mod positions {    
+pub(crate) mod positions {    
     use cube::Move;
     use cube::Macro;
     use permutations;
@@ -89,7 +66,7 @@ mod positions {
         assert!((i ^ j).count_ones() == 1);
         let f = ((i & !(i^j)).count_ones() & 1) as u8; // Number of inversions
         let d = |k| (k + ((i^j) >> 1)) % 3;
-        let flip = |k: u8| (((i & (!(i^j))) >> d(k)) & 1u8) as u8;
+        let flip = |k: u8| ((i & (!(i^j))) >> d(k)) & 1u8;
         let m = |k, s, c: u8| Move::from(2u8*3u8*d(k) + 3u8*(s^flip(k)) + ((2u8 + f + (c << f)) % 3u8));
         [m(2, 0, 3), m(0, 1, 3), m(1, 1, 3),
          m(0, 1, 1), m(1, 1, 1), m(2, 0, 1), m(0, 1, 3),
@@ -128,65 +105,72 @@ mod positions {
         m
     }
 
-    pub fn test_solve(p: [u8; 8]) {
-        let mut q = p;
-        for m in solve_macro(p).move_iter() {
-            apply(&mut q, m);
-        }
-        assert!(q == permutations::IDENTITY);
-    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-    pub fn test_swap(i: u8, j: u8, m: Macro) {
-        let mut p = permutations::IDENTITY;
-        for m in m.move_iter() {
-            apply(&mut p, m);
+        fn test_solve(p: [u8; 8]) {
+            let mut q = p;
+            for m in solve_macro(p).move_iter() {
+                apply(&mut q, m);
+            }
+            assert!(q == permutations::IDENTITY);
         }
-        for k in 0 .. 8u8 {
-            if k == i {
-                assert!(p[k as usize] == j);
-            } else if k == j {
-                assert!(p[k as usize] == i);
-            } else {
-                assert!(p[k as usize] == k);
+
+        fn test_swap(i: u8, j: u8, m: Macro) {
+            let mut p = permutations::IDENTITY;
+            for m in m.move_iter() {
+                apply(&mut p, m);
+            }
+            for k in 0 .. 8u8 {
+                if k == i {
+                    assert!(p[k as usize] == j);
+                } else if k == j {
+                    assert!(p[k as usize] == i);
+                } else {
+                    assert!(p[k as usize] == k);
+                }
             }
         }
-    }
-    
-    
-    pub fn test_swaps() {
-        use solver::positions::*;
-        for i in 0 .. 8 {
-            for j in 0 .. 8 {
-                if i != j {
-                    match (i^j as u8).count_ones() {
-                        3 => {
-                            test_swap(i, j, Macro::from(swap_opposite_moves(i, j)));
-                        },
-                        2 => {
-                            test_swap(i, j, Macro::from(swap_diag_moves(i, j)));
-                        },
-                        1 => {
-                            test_swap(i, j, Macro::from(swap_edge_moves(i, j)));
+
+        #[test]
+        fn swaps() {
+            for i in 0 .. 8u8 {
+                for j in 0 .. 8u8 {
+                    if i != j {
+                        match (i^j).count_ones() {
+                            3 => {
+                                test_swap(i, j, Macro::from(swap_opposite_moves(i, j)));
+                            },
+                            2 => {
+                                test_swap(i, j, Macro::from(swap_diag_moves(i, j)));
+                            },
+                            1 => {
+                                test_swap(i, j, Macro::from(swap_edge_moves(i, j)));
+                            }
+                            _ => panic!(),
                         }
-                        _ => panic!(),
                     }
                 }
             }
         }
+
+        #[test]
+        fn solves() {
+            test_solve([0, 1, 2, 3, 4, 5, 6, 7]);
+            test_solve([0, 1, 2, 4, 3, 5, 6, 7]);
+            test_solve([0, 7, 2, 4, 3, 5, 6, 1]);
+            test_solve([6, 7, 5, 2, 3, 4, 0, 1]);
+        }
     }
-    
 }
 
-mod orientations {
+pub(crate) mod orientations {
     use solver::positions;
     use cube::Move;
     use cube::Macro;
     use permutations;
     
-    pub fn identity() -> [u8; 8] {
-        [0; 8]
-    }
-
     pub fn apply(o: &mut [u8; 8], m: &Move) {
 
         let f = (*m as u8) / 3;
@@ -205,9 +189,9 @@ mod orientations {
             }
         };
         let mb = Move::from(f*3);
+        let mut p = permutations::IDENTITY;
+        positions::apply(&mut p, &mb);
         for _ in 0..c {
-            let mut p = permutations::IDENTITY;
-            positions::apply(&mut p, &mb);
             permutations::apply(o, &p);
             for i in 0..8 {
                 o[i] = (o[i] + changes[i]) % 3;
@@ -250,76 +234,6 @@ mod orientations {
         ]
     }
     
-    pub fn test_apply() {
-        for s in 0..6 {
-            use cube::Move::*;
-            let m = Move::from(3*s);
-            let mut o = identity();
-            apply(&mut o, &m);
-            match m {
-                L1 => assert!(o == [0; 8]),
-                R1 => assert!(o == [0; 8]),
-                D1 => assert!(o == [2, 1, 0, 0, 1, 2, 0, 0]),
-                U1 => assert!(o == [0, 0, 1, 2, 0, 0, 2, 1]),
-                B1 => assert!(o == [1, 2, 2, 1, 0, 0, 0, 0]),
-                F1 => assert!(o == [0, 0, 0, 0, 2, 1, 1, 2]),
-                _ => panic!(),
-            }
-            let mi = Move::from(3*s + 2);
-            apply(&mut o, &mi);
-            assert!(o == [0; 8]);
-        }
-    }
-    
-    pub fn test_twist(i: u8, j: u8) {
-        let mut twist_macro = Macro::identity();
-        use solver::orientations; 
-        let distinct_side_count = (i^j).count_ones();
-        match distinct_side_count {
-            1 => {
-                twist_macro.moves.extend_from_slice(&orientations::twist_edge_moves(i, j));
-            },
-            2 => {
-                twist_macro.moves.extend_from_slice(&orientations::twist_diag_moves(i, j));
-            },
-            3 => {
-                twist_macro.moves.extend_from_slice(&orientations::twist_opposite_moves(i, j));
-            },
-            _ => panic!(),
-        }
-
-        {
-            let mut o = orientations::identity();
-            let mut p = permutations::IDENTITY;
-            for m in twist_macro.move_iter() {
-                orientations::apply(&mut o, m);
-                positions::apply(&mut p, m);
-            }
-            
-            let mut correct: [u8; 8] = [0; 8];
-            for k in 0..8 {
-                if k == i {
-                    correct[k as usize] = 1;
-                } else if k == j {
-                    correct[k as usize] = 2;
-                }
-            }
-
-            assert!(o == correct);
-            assert!(p == permutations::IDENTITY);
-        }
-    }
-    
-    pub fn test_twists() {
-        for i in 0 .. 8 {
-            for j in 0 .. 8 {
-                if i != j {
-                    test_twist(i, j);
-                }
-            }
-        }
-    }
-
     pub fn solve_macro(o: [u8; 8]) -> Macro {
         let mut m = Macro::identity();
         for k in 1..8u8 {
@@ -350,12 +264,136 @@ mod orientations {
         m
     }
 
-    pub fn test_solve(o: [u8; 8]) {
-        let mut s = o;
-        let mac = solve_macro(o);
-        for m in mac.move_iter() {
-            apply(&mut s, m);
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn identity() -> [u8; 8] {
+            [0; 8]
+        }
+
+        fn test_solve(o: [u8; 8]) {
+            let mut s = o;
+            let mac = solve_macro(o);
+            for m in mac.move_iter() {
+                apply(&mut s, m);
+            }
+            assert!(s == identity());
         }
-        assert!(s == identity());
+
+        fn test_twist(i: u8, j: u8) {
+            let mut twist_macro = Macro::identity();
+            use solver::orientations; 
+            let distinct_side_count = (i^j).count_ones();
+            match distinct_side_count {
+                1 => {
+                    twist_macro.moves.extend_from_slice(&orientations::twist_edge_moves(i, j));
+                },
+                2 => {
+                    twist_macro.moves.extend_from_slice(&orientations::twist_diag_moves(i, j));
+                },
+                3 => {
+                    twist_macro.moves.extend_from_slice(&orientations::twist_opposite_moves(i, j));
+                },
+                _ => panic!(),
+            }
+
+            {
+                let mut o = identity();
+                let mut p = permutations::IDENTITY;
+                for m in twist_macro.move_iter() {
+                    orientations::apply(&mut o, m);
+                    positions::apply(&mut p, m);
+                }
+            
+                let mut correct: [u8; 8] = [0; 8];
+                for k in 0..8 {
+                    if k == i {
+                        correct[k as usize] = 1;
+                    } else if k == j {
+                        correct[k as usize] = 2;
+                    }
+                }
+
+                assert!(o == correct);
+                assert!(p == permutations::IDENTITY);
+            }
+        }
+
+        #[test]
+        fn applies() {
+            for s in 0..6 {
+                use cube::Move::*;
+                let m = Move::from(3*s);
+                let mut o = identity();
+                apply(&mut o, &m);
+                match m {
+                    L1 => assert!(o == [0; 8]),
+                    R1 => assert!(o == [0; 8]),
+                    D1 => assert!(o == [2, 1, 0, 0, 1, 2, 0, 0]),
+                    U1 => assert!(o == [0, 0, 1, 2, 0, 0, 2, 1]),
+                    B1 => assert!(o == [1, 2, 2, 1, 0, 0, 0, 0]),
+                    F1 => assert!(o == [0, 0, 0, 0, 2, 1, 1, 2]),
+                    _ => panic!(),
+                }
+                let mi = Move::from(3*s + 2);
+                apply(&mut o, &mi);
+                assert!(o == [0; 8]);
+            }
+        }
+
+        #[test]
+        fn twists() {
+            for i in 0 .. 8 {
+                for j in 0 .. 8 {
+                    if i != j {
+                        test_twist(i, j);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn solves() {
+            test_solve([0, 1, 2, 0, 1, 2, 0, 0]);
+            test_solve([1, 1, 1, 2, 2, 2, 1, 2]);
+            test_solve([1, 1, 1, 0, 2, 2, 0, 2]);
+            test_solve([0, 0, 0, 0, 0, 0, 0, 0]);
+            test_solve([2, 0, 0, 0, 0, 0, 0, 1]);
+            test_solve([2, 0, 0, 1, 1, 1, 0, 1]);
+            test_solve([2, 2, 2, 2, 2, 2, 2, 1]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrays_agree_with_cube() {
+        // Positions are indexed by cubie in the arrays, but by position in 'positions_orientations'.
+        let scramble: cube::Macro = "R U' F2 L D B' R2 U F' D2".parse().unwrap();
+        let scrambled_cube = cube::Cube::solved().sequence_moves(scramble.move_iter());
+        for start_cube in [cube::Cube::solved(), scrambled_cube].iter() {
+            let (start_positions, start_orientations) = start_cube.positions_orientations();
+            for idx in 0..18u8 {
+                let m = cube::Move::from(idx);
+                let mut positions = permutations::inverse(start_positions);
+                let mut orientations = start_orientations;
+                positions::apply(&mut positions, &m);
+                orientations::apply(&mut orientations, &m);
+                let (cube_positions, cube_orientations) = start_cube.sequence(m).positions_orientations();
+                assert_eq!(positions, permutations::inverse(cube_positions));
+                assert_eq!(orientations, cube_orientations);
+            }
+        }
+    }
+
+    #[test]
+    fn solves_cube() {
+        let scramble: cube::Macro = "F R' U2 L D2 B R U' F2 L' D".parse().unwrap();
+        let cube = cube::Cube::solved().sequence_moves(scramble.move_iter());
+        assert_eq!(cube.sequence_moves(solution(&cube).iter()), cube::Cube::solved());
     }
 }
@@ -1,16 +1,58 @@
 use std;
 use cube;
-use winapi;
-use error;
-use log;
+use optimal;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Input {
     Forward,
     Back,
     Exit,
 }
 
+// Where the viewer gets its input from, and shows its output.
+pub trait Console
+{
+    // Waits until there is input that the viewer understands.
+    fn wait_for_input(&mut self) -> Input;
+    // Replaces what was shown before with 'frame'.
+    fn show(&mut self, frame: &str);
+    // Returns the error that made the console ask the viewer to exit, if any, so that it can be reported once the
+    // console has been restored.
+    fn take_error(&mut self) -> Option<String>
+    {
+        None
+    }
+}
+
+// A console that plays back a fixed list of inputs, and records every frame shown, for testing the viewer without
+// a terminal. Once the inputs run out, it asks the viewer to exit.
+pub struct ScriptedConsole
+{
+    pub inputs: std::collections::VecDeque<Input>,
+    pub frames: Vec<String>,
+}
+
+impl ScriptedConsole
+{
+    pub fn new(inputs: &[Input]) -> ScriptedConsole
+    {
+        ScriptedConsole {inputs: inputs.iter().cloned().collect(), frames: Vec::new()}
+    }
+}
+
+impl Console for ScriptedConsole
+{
+    fn wait_for_input(&mut self) -> Input
+    {
+        self.inputs.pop_front().unwrap_or(Input::Exit)
+    }
+
+    fn show(&mut self, frame: &str)
+    {
+        self.frames.push(String::from(frame));
+    }
+}
+
 pub struct SideAliases
 {
     pub aliases: std::collections::HashMap<String, cube::Side>
@@ -18,6 +60,17 @@ pub struct SideAliases
 
 impl SideAliases
 {
+    // Aliases each side by the first letter of its name.
+    pub fn side_initials() -> SideAliases
+    {
+        let mut aliases = std::collections::HashMap::new();
+        for side_idx in 0..6 {
+            let side = cube::Side::from(side_idx);
+            aliases.insert(String::from(&cube::Side::serialization(side)[0..1]), side);
+        }
+        SideAliases{aliases}
+    }
+
     pub fn alias(&self, side: cube::Side) -> String
     {
         for (a, s) in self.aliases.iter() {
@@ -34,74 +87,105 @@ impl SideAliases
     }
 }
 
+// The moves shown together on one page of the viewer, with the name of the step they make, if any.
+pub struct Section
+{
+    pub heading: Option<String>,
+    pub moves: cube::Macro,
+}
+
+// Splits the moves into pages of 4, without headings.
+pub fn pages_of_moves(solution_moves: &[cube::Move]) -> Vec<Section>
+{
+    let page_moves_count = 4;
+    solution_moves.chunks(page_moves_count)
+        .map(|moves| Section {heading: None, moves: cube::Macro::from(moves)})
+        .collect()
+}
+
 pub fn run_main_loop(
     aliases: &SideAliases,
     starting_cube: &cube::Cube,
-    console_context: &ConsoleContext,
-    solution_moves: &Vec<cube::Move>
+    console: &mut dyn Console,
+    sections: &[Section],
+    simplification_saved_moves_count: usize
 )
 {
-    let page_moves_count = 4;
+    let solution_moves: Vec<cube::Move> =
+        sections.iter().flat_map(|section| section.moves.move_iter().cloned()).collect();
+    // Rotations don't count towards the length.
+    let solution_face_moves: Vec<cube::Move> = sections.iter().flat_map(|section| section.moves.face_moves()).collect();
     let mut page_idx = 0;
 
-    if solution_moves.len() > 0 {
+    if !sections.is_empty() {
         loop {
-            use std::cmp::min;
-
-            let pages_count = (solution_moves.len() + 1) / page_moves_count;
-            assert!(pages_count > 0);
+            let pages_count = sections.len();
             
             // Render
             {
-                console_context.clear();
-
-                println!("Progress: {}/{}", page_idx + 1, pages_count);
-                print!("\n\n\n");
+                let mut frame = String::new();
+
+                frame.push_str(&format!("Progress: {}/{}\n", page_idx + 1, pages_count));
+                frame.push_str(&format!(
+                    "Solution length: {} (half-turn metric), {} (quarter-turn metric)\n",
+                    optimal::Metric::HalfTurn.length(&solution_face_moves),
+                    optimal::Metric::QuarterTurn.length(&solution_face_moves)
+                ));
+                frame.push_str(&format!("Simplification saved {} moves\n", simplification_saved_moves_count));
+                match sections[page_idx].heading {
+                    Some(ref heading) => frame.push_str(&format!("\n{}\n\n", heading)),
+                    None => frame.push_str("\n\n\n"),
+                }
                 
-                let page_moves_lo_idx = page_moves_count * page_idx;                
-                let page_moves_hi_idx =
-                    min(solution_moves.len(), page_moves_lo_idx + page_moves_count);
+                let page_moves_lo_idx: usize =
+                    sections[0..page_idx].iter().map(|section| section.moves.moves.len()).sum();
+                let page_moves_hi_idx = page_moves_lo_idx + sections[page_idx].moves.moves.len();
 
                 {
                     let cube = starting_cube.sequence_moves(solution_moves[0..page_moves_lo_idx].iter());
-                    print_diagram(&mut |side| aliases.alias(side), &cube);
+                    print_diagram(&mut frame, &mut |side| aliases.alias(side), &cube);
                 }                
 
-                print!("\n\n");
-                let page_moves = &solution_moves[page_moves_lo_idx .. page_moves_hi_idx];
-                for m in page_moves {                    
-                    print!("{:?}  ", m);
+                frame.push_str("\n\n");
+                let page_tokens = sections[page_idx].moves.tokens();
+                for token in page_tokens.iter() {
+                    frame.push_str(&format!("{}  ", token));
+                }
+                // A rotation turns the whole cube, so that the moves after it are named after where the sides are
+                // then.
+                if page_tokens.iter().any(|token| matches!(token, &cube::Token::Rotation(_))) {
+                    frame.push_str("\n(x, y and z rotate the whole cube like R, U and F)");
                 }
 
-                print!("\n\n");
+                frame.push_str("\n\n");
                 
                 {
                     let cube = starting_cube.sequence_moves(solution_moves[0..page_moves_hi_idx].iter());
-                    print_diagram(&mut |side| aliases.alias(side), &cube);
+                    print_diagram(&mut frame, &mut |side| aliases.alias(side), &cube);
                 }
 
 
-                println!("\n\n\n");
-                println!("Left key: previous page");
-                println!("Right key: next page");
-                println!("Escape: exit");
+                frame.push_str("\n\n\n\n");
+                frame.push_str("Left key: previous page\n");
+                frame.push_str("Right key: next page\n");
+                frame.push_str("Escape: exit\n");
+
+                console.show(&frame);
             }
             
             // Get input
-            let input = console_context.wait_for_input();
+            let input = console.wait_for_input();
             
             // Advance state
             {
                 match input {
                     Input::Forward => {
                         if page_idx < pages_count - 1 {
-                            page_idx = page_idx + 1;
+                            page_idx += 1;
                         }
                     },
                     Input::Back => {
-                        if page_idx > 0 {
-                            page_idx = page_idx - 1;
-                        }
+                        page_idx = page_idx.saturating_sub(1);
                     },
                     Input::Exit => {
                         break;
@@ -114,278 +198,7 @@ pub fn run_main_loop(
 }
 
 
-
-pub struct ConsoleContext {
-    buffer_width: u32,
-    buffer_height: u32,
-    character_attributes: winapi::shared::minwindef::WORD,
-    output_device_handle: winapi::um::winnt::HANDLE,
-    input_device_handle: winapi::um::winnt::HANDLE,
-    initial_console_mode: winapi::shared::minwindef::DWORD,
-}
-
-pub enum ConsoleContextInitializationError
-{
-    StandardInputDeviceHandleIsInvalid{error_code: winapi::shared::minwindef::DWORD},
-    StandardOutputDeviceHandleIsInvalid{error_code: winapi::shared::minwindef::DWORD},
-    StandardInputDeviceDoesNotExist,
-    StandardOutputDeviceDoesNotExist,
-}
-
-pub enum ConsoleContextDeinitializationError
-{
-    FailedToResetConsoleMode{error_code: winapi::shared::minwindef::DWORD},
-}
-
-impl ConsoleContextDeinitializationError
-{
-    pub fn message(&self) -> String
-    {
-        use self::ConsoleContextDeinitializationError::*;
-        match self
-        {
-            &FailedToResetConsoleMode{error_code} =>
-                format!("failed to reset console mode: error code: {}", error_code),
-        }
-    }
-}
-
-impl ConsoleContextInitializationError
-{
-    pub fn message(&self) -> String
-    {
-        use self::ConsoleContextInitializationError::*;
-        match self
-        {
-            &StandardOutputDeviceHandleIsInvalid{error_code} =>
-                format!("Output device handle is invalid: error code: {}", error_code),
-            &StandardInputDeviceHandleIsInvalid{error_code} =>
-                format!("Input device handle is invalid: error code: {}", error_code),
-            &StandardOutputDeviceDoesNotExist =>
-                String::from("There is no standard output device"),
-            &StandardInputDeviceDoesNotExist =>
-                String::from("There is no standard input device"),
-        }
-    }
-}
-
-impl ConsoleContext {
-
-    fn wait_for_input(&self) -> Input {
-        use winapi::um::winuser::*;
-        use winapi::um::wincon::*;
-        use winapi::um::consoleapi::*;
-        use winapi::um::synchapi::*;
-        use winapi::shared::minwindef::*;
-        use winapi::um::winbase::*;
-
-        loop {
-            {
-                let timeout_duration = INFINITE;
-                let result =
-                    unsafe {
-                        WaitForSingleObject(
-                            self.input_device_handle,
-                            timeout_duration
-                        )
-                    };
-                if result != WAIT_OBJECT_0 {
-                    log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-                }
-            }
-        
-            {
-                let mut input_records: [INPUT_RECORD; 128] = unsafe{std::mem::uninitialized()};
-                let mut read_event_count = 0;
-                let result =
-                    unsafe {
-                        ReadConsoleInputW(
-                            self.input_device_handle,
-                            input_records.as_mut_ptr(),
-                            input_records.len() as u32,
-                            &mut read_event_count
-                        )
-                    };
-                if result == 0 {
-                    log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-                }
-
-                for read_event_idx in 0..read_event_count {
-                    let input_record = &input_records[read_event_idx as usize];
-                    match input_record.EventType {
-                        KEY_EVENT => {
-                            let event = unsafe { input_record.Event.KeyEvent() };
-                            if (event.wVirtualKeyCode as i32) == VK_RIGHT && event.bKeyDown == TRUE {
-                                return Input::Forward;
-                            }
-                            if (event.wVirtualKeyCode as i32) == VK_LEFT && event.bKeyDown == TRUE {
-                                return Input::Back;
-                            }
-                            if (event.wVirtualKeyCode as i32) == VK_ESCAPE && event.bKeyDown == TRUE {
-                                return Input::Exit;
-                            }                            
-                        },
-                        _ => {},
-                    }
-                }
-            }
-        }
-    }
-    
-    pub fn try_initialize() -> Result<ConsoleContext, ConsoleContextInitializationError> {
-
-        use winapi::um::winbase::*;
-        use winapi::um::handleapi::*;
-        use winapi::um::consoleapi::*;
-        use winapi::um::winnt::HANDLE;
-        use self::ConsoleContextInitializationError::*;
-        let output_device_handle = unsafe {
-            let standard_device_handle = STD_OUTPUT_HANDLE;
-            winapi::um::processenv::GetStdHandle(standard_device_handle) as HANDLE
-        };
-        if output_device_handle == INVALID_HANDLE_VALUE {
-            let error_code = unsafe { winapi::um::errhandlingapi::GetLastError() };
-            return Err(StandardOutputDeviceHandleIsInvalid{error_code});
-        }
-        if output_device_handle == (0 as HANDLE) {
-            return Err(StandardOutputDeviceDoesNotExist);
-        }
-
-        let input_device_handle = unsafe {
-            let standard_device_handle = STD_INPUT_HANDLE;
-            winapi::um::processenv::GetStdHandle(standard_device_handle) as HANDLE
-        };
-        if input_device_handle == INVALID_HANDLE_VALUE {
-            let error_code = unsafe { winapi::um::errhandlingapi::GetLastError() };
-            return Err(StandardInputDeviceHandleIsInvalid{error_code});
-        }
-        if input_device_handle == (0 as HANDLE) {
-            return Err(StandardInputDeviceDoesNotExist);
-        }
-        
-        let console_screen_buffer_info = {
-            let mut info = unsafe{std::mem::uninitialized()};
-            let result = unsafe{winapi::um::wincon::GetConsoleScreenBufferInfo(output_device_handle, &mut info)};
-            if result == 0 {
-                let error_code = unsafe {winapi::um::errhandlingapi::GetLastError()};
-                if error_code == winapi::shared::winerror::ERROR_INVALID_HANDLE {
-                    assert!(output_device_handle != INVALID_HANDLE_VALUE);
-                    assert!(output_device_handle != 0 as HANDLE);
-                    // It seems that this can happen even if output device handle is valid.
-                    // GetConsoleScreenBufferInfo mentions that the output device must have GENERIC_READ access
-                    // rights. I believe this error happens when that's not fulfilled, but I could not find it
-                    // mentioned in the documentation.
-                    // A more descriptive error message could be given if it could be determined that we do not have
-                    // GENERIC_READ access, but that seems pretty complicated to do.
-                    error::report_fatal_error_and_exit(
-                        &String::from("Unable to access screen buffer info for the console's standard output device.")
-                    );
-                }
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-            }
-            info
-        };        
-
-        // Record in order to restore before exit
-        let initial_console_mode = {
-            let mut mode = 0;
-            let result = unsafe{ GetConsoleMode(input_device_handle, &mut mode) };
-            if result == 0 {
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-            }
-            mode
-        };
-
-        {
-            let mode = winapi::um::wincon::ENABLE_WINDOW_INPUT;
-            let result = unsafe { SetConsoleMode(input_device_handle, mode) };
-            if result == 0 {
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-            }
-        }
-        
-        Ok(
-            ConsoleContext {
-                output_device_handle: output_device_handle,
-                input_device_handle: input_device_handle,
-                buffer_width: console_screen_buffer_info.dwSize.X as u32,
-                buffer_height: console_screen_buffer_info.dwSize.Y as u32,
-                character_attributes: console_screen_buffer_info.wAttributes,
-                initial_console_mode: initial_console_mode,
-            }
-        )
-    }
-
-    pub fn try_deinitialize(&self) -> Option<ConsoleContextDeinitializationError> {
-        use winapi::um::consoleapi::*;
-        use self::ConsoleContextDeinitializationError::*;
-        // Restore the initial console mode.
-        {
-            let result = unsafe { SetConsoleMode(self.input_device_handle, self.initial_console_mode) };
-            if result == 0 {
-                let error_code = unsafe { winapi::um::errhandlingapi::GetLastError() };
-                return Some(FailedToResetConsoleMode{error_code});
-            }
-        }
-
-        return None;
-    }
-    
-    #[cfg(windows)]
-    fn clear(&self) {
-        use winapi::um::wincon::*;
-        let top_left_cell_coordinates = COORD {X: 0, Y: 0};
-        let fill_length = (self.buffer_width) * (self.buffer_height);
-        
-        {
-            let character = ' ' as u16;
-            let mut written_characters_count = 0;
-            let result = unsafe {
-                winapi::um::wincon::FillConsoleOutputCharacterW(
-                    self.output_device_handle,
-                    character,
-                    fill_length,
-                    top_left_cell_coordinates,
-                    &mut written_characters_count
-                )
-            };
-            if result == 0 {
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());            
-            }
-            assert!(written_characters_count == fill_length);
-        }
-
-        {
-            let mut written_attributes_count = unsafe{std::mem::uninitialized()};
-            let result = unsafe {
-                winapi::um::wincon::FillConsoleOutputAttribute(
-                    self.output_device_handle,
-                    self.character_attributes,
-                    fill_length,
-                    top_left_cell_coordinates,
-                    &mut written_attributes_count
-                )
-            };
-            assert!(written_attributes_count == fill_length);
-            if result == 0 {
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-            }
-        }
-
-        {
-            let result = unsafe {
-                winapi::um::wincon::SetConsoleCursorPosition(self.output_device_handle, top_left_cell_coordinates)
-            };
-            if result == 0 {
-                log::log_get_last_error_code_and_exit(file!(), line!(), column!());
-            }
-        }
-    }
-    
-    
-}
-
-fn print_diagram(side_alias: &mut FnMut(cube::Side) -> String, cube: &cube::Cube) {
+fn print_diagram(frame: &mut String, side_alias: &mut dyn FnMut(cube::Side) -> String, cube: &cube::Cube) {
     use cube::Side::*;
     let column_count = 2*4;
     let row_count = 2*3;
@@ -420,17 +233,103 @@ fn print_diagram(side_alias: &mut FnMut(cube::Side) -> String, cube: &cube::Cube
                     assert!(position[1].abs() == 1);
                     assert!(position[2].abs() == 1);
                     let target_normal = cube.transform(position).inverse().apply(&cube::normal(side));
-                    format!("{}", side_alias(cube::normal_side(target_normal)))
+                    side_alias(cube::normal_side(target_normal))
                 } else {
                     " ".to_string()
                 }
             };
-            print!("{}", c);
+            frame.push_str(&c);
             if column_idx < column_count - 1 {
-                print!(" ");
+                frame.push(' ');
             }
         }
-        println!("");
+        frame.push('\n');
     }
 }
 
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn paging_stays_within_the_solution()
+    {
+        use cube::Move::*;
+        let moves = [R1, U1, F1, R3, U3, F3];
+        let starting_cube = cube::Cube::solved().sequence_moves(moves.iter());
+        let solution_moves: Vec<cube::Move> = moves.iter().rev().map(|m| m.inverse()).collect();
+        let mut console = ScriptedConsole::new(
+            &[Input::Back, Input::Forward, Input::Forward, Input::Back]
+        );
+        let sections = pages_of_moves(&solution_moves);
+        run_main_loop(&SideAliases::side_initials(), &starting_cube, &mut console, &sections, 0);
+
+        let progress: Vec<&str> = console.frames.iter().map(|frame| frame.lines().next().unwrap()).collect();
+        assert_eq!(progress, ["Progress: 1/2", "Progress: 1/2", "Progress: 2/2", "Progress: 2/2", "Progress: 1/2"]);
+    }
+
+    #[test]
+    fn last_page_shows_remaining_moves_and_solved_cube()
+    {
+        use cube::Move::*;
+        let solution_moves = [L1, L1, L1, L1, D2];
+        let starting_cube = cube::Cube::solved().sequence(D2);
+        let mut console = ScriptedConsole::new(&[Input::Forward]);
+        let sections = pages_of_moves(&solution_moves);
+        run_main_loop(&SideAliases::side_initials(), &starting_cube, &mut console, &sections, 0);
+
+        let last_frame = console.frames.last().unwrap();
+        assert!(last_frame.contains("\n\nD2  \n\n"));
+        assert!(last_frame.ends_with(
+            "    u u        \n\
+             \x20   u u        \n\
+             l l f f r r b b\n\
+             l l f f r r b b\n\
+             \x20   d d        \n\
+             \x20   d d        \n\
+             \n\n\n\n\
+             Left key: previous page\n\
+             Right key: next page\n\
+             Escape: exit\n"
+        ));
+    }
+
+    #[test]
+    fn sections_show_their_headings()
+    {
+        use cube::Move::*;
+        let sections = [
+            Section {heading: Some(String::from("Face")), moves: cube::Macro::from(vec![R1, U1, F1, R3, U3])},
+            Section {heading: Some(String::from("OLL (skip)")), moves: cube::Macro::identity()},
+        ];
+        let mut console = ScriptedConsole::new(&[Input::Forward, Input::Forward]);
+        run_main_loop(&SideAliases::side_initials(), &cube::Cube::solved(), &mut console, &sections, 0);
+
+        let progress: Vec<&str> = console.frames.iter().map(|frame| frame.lines().next().unwrap()).collect();
+        assert_eq!(progress, ["Progress: 1/2", "Progress: 2/2", "Progress: 2/2"]);
+        assert!(console.frames[0].contains("moves\n\nFace\n\n"));
+        assert!(console.frames[0].contains("\n\nR'  U'  F'  R  U  \n\n"));
+        assert!(console.frames[1].contains("moves\n\nOLL (skip)\n\n"));
+    }
+
+    #[test]
+    fn rotations_are_labelled()
+    {
+        let sections = [Section {heading: None, moves: "y R U R'".parse().unwrap()}];
+        let mut console = ScriptedConsole::new(&[]);
+        run_main_loop(&SideAliases::side_initials(), &cube::Cube::solved(), &mut console, &sections, 0);
+
+        assert!(console.frames[0].contains("Solution length: 3 (half-turn metric), 3 (quarter-turn metric)\n"));
+        assert!(console.frames[0].contains("\n\ny  R  U  R'  \n(x, y and z rotate the whole cube like R, U and F)\n"));
+    }
+
+    #[test]
+    fn empty_solution_shows_nothing()
+    {
+        let mut console = ScriptedConsole::new(&[Input::Forward]);
+        run_main_loop(&SideAliases::side_initials(), &cube::Cube::solved(), &mut console, &pages_of_moves(&[]), 0);
+        assert!(console.frames.is_empty());
+    }
+}
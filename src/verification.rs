@@ -0,0 +1,122 @@
+use std;
+use cube;
+use cube::Move;
+use coordinates;
+use coordinates::Coordinates;
+use permutations;
+use solver;
+
+/// Solution lengths of the macro solver over a set of states.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub state_count: u64,
+    /// Entry 'n' of 'length_histogram' is the number of states whose solution has 'n' moves.
+    pub length_histogram: Vec<u64>,
+}
+
+impl Report {
+    fn new() -> Report {
+        Report {state_count: 0, length_histogram: vec![]}
+    }
+
+    fn record(&mut self, length: usize) {
+        if self.length_histogram.len() <= length {
+            self.length_histogram.resize(length + 1, 0);
+        }
+        self.length_histogram[length] += 1;
+        self.state_count += 1;
+    }
+
+    fn merge(&mut self, other: &Report) {
+        for (length, &count) in other.length_histogram.iter().enumerate() {
+            if count > 0 {
+                if self.length_histogram.len() <= length {
+                    self.length_histogram.resize(length + 1, 0);
+                }
+                self.length_histogram[length] += count;
+            }
+        }
+        self.state_count += other.state_count;
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.length_histogram.iter().rposition(|&count| count > 0).unwrap_or(0)
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        let total_length: u64 =
+            self.length_histogram.iter().enumerate().map(|(length, &count)| length as u64 * count).sum();
+        total_length as f64 / self.state_count as f64
+    }
+}
+
+/// A state that the macro solver failed to solve.
+#[derive(Debug)]
+pub struct Failure {
+    pub coordinates: Coordinates,
+    pub moves: Vec<Move>,
+}
+
+impl Failure {
+    pub fn message(&self) -> String {
+        format!(
+            "the solution {} does not solve the state with permutation {} and orientation {}",
+            ::cube::Macro::from(self.moves.as_slice()), self.coordinates.permutation, self.coordinates.orientation
+        )
+    }
+}
+
+/// Runs the macro solver on every one of the 8!*3^7 states of the cube, that is, every permutation of the
+/// corners with every orientation of them that adds up to a whole number of turns, and checks that each solution
+/// solves its state. The permutations are split between 'thread_count' threads.
+pub fn verify_macro_solver(thread_count: usize) -> Result<Report, Failure> {
+    assert!(thread_count > 0);
+    let threads: Vec<std::thread::JoinHandle<Result<Report, Failure>>> = (0..thread_count).map(|thread_idx| {
+        std::thread::spawn(move || {
+            let ranks = (thread_idx..coordinates::PERMUTATION_COUNT).step_by(thread_count);
+            verify_permutations(ranks)
+        })
+    }).collect();
+
+    let mut report = Report::new();
+    for thread in threads {
+        report.merge(&thread.join().unwrap()?);
+    }
+    Ok(report)
+}
+
+// Verifies the states with the given permutation ranks, in every orientation.
+fn verify_permutations<I>(ranks: I) -> Result<Report, Failure>
+where
+    I: Iterator<Item = usize>
+{
+    let mut report = Report::new();
+    for rank in ranks {
+        // The cube lists the cubie at each position, rather than the position of each cubie.
+        let positions = permutations::inverse(coordinates::permutation_from_rank(rank as u16));
+        for orientation in 0..coordinates::ORIENTATION_COUNT {
+            let start = Coordinates {permutation: rank as u16, orientation: orientation as u16};
+            let orientations = coordinates::orientations_from_coordinate(start.orientation);
+            let cube = cube::Cube::from_positions_orientations(positions, orientations).unwrap();
+            let moves = solver::solution(&cube);
+            if !cube.sequence_moves(moves.iter()).is_solved() {
+                return Err(Failure {coordinates: start, moves});
+            }
+            report.record(moves.len());
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_some_permutations() {
+        let report = verify_permutations([0, 1, 5039, 5040, 40319].iter().cloned()).unwrap();
+        assert_eq!(report.state_count, 5*coordinates::ORIENTATION_COUNT as u64);
+        assert_eq!(report.length_histogram[0], 1);
+        assert!(report.max_length() > 0 && report.mean_length() < report.max_length() as f64);
+    }
+}
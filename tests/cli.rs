@@ -0,0 +1,154 @@
+use std::process::Command;
+use std::process::Output;
+
+fn run(arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rubik2x2")).args(arguments).output().unwrap()
+}
+
+#[test]
+fn prints_solution_of_scramble() {
+    let output = run(&["--scramble=R U F'", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "F U' R'\n(3 HTM, 3 QTM)\n");
+}
+
+#[test]
+fn prints_solution_length_in_every_format() {
+    let expected_outputs = [
+        ("--output=plain", "R2 F'\n(2 HTM, 3 QTM)\n"),
+        ("--output=lines", "R2\nF'\n(2 HTM, 3 QTM)\n"),
+        ("--output=csv", "moves,half_turn,quarter_turn\nR2 F',2,3\n"),
+        (
+            "--output=json",
+            "{\"moves\": [\"R2\", \"F'\"], \"length\": {\"half_turn\": 2, \"quarter_turn\": 3}, \
+             \"simplification_saved\": 0}\n"
+        ),
+    ];
+    for &(format_argument, expected_output) in expected_outputs.iter() {
+        let output = run(&["--scramble=F R2", format_argument]);
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected_output);
+    }
+}
+
+#[test]
+fn invalid_input_exits_with_status_1() {
+    for arguments in [&["--metric=slice-turn"][..], &["--scramble=R X", "--output=plain"][..], &["l=left"][..]].iter() {
+        let output = run(arguments);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+    }
+}
+
+#[test]
+fn inapplicable_options_exit_with_status_1() {
+    let cases = [
+        &["--solver=macro", "--metric=quarter-turn", "--scramble=R U", "--output=plain"][..],
+        &["--solver=ortega", "--table=distances.table", "--scramble=R U", "--output=plain"][..],
+        &["--solver=beginner", "--generators=RU", "--scramble=R U", "--output=plain"][..],
+        &["--solver=ortega", "--turns=half", "--scramble=R U", "--output=plain"][..],
+        &["--solver=beginner", "--costs=costs.txt", "--scramble=R U", "--output=plain"][..],
+        &["verify", "--output=plain"][..],
+        &["build-table", "--output=plain", "distances.table"][..],
+        &["depths", "--metric=quarter-turn"][..],
+    ];
+    for arguments in cases.iter() {
+        let output = run(arguments);
+        assert_eq!(output.status.code(), Some(1), "{:?}", arguments);
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+    }
+}
+
+#[test]
+fn unsolvable_configuration_exits_with_status_2() {
+    // The left-up-front corner has two up stickers.
+    let output = run(&[
+        "--output=plain",
+        "l=left", "r=right", "d=down", "u=up", "b=back", "f=front",
+        "left{l,l,l,u}", "right{r,r,r,r}", "down{d,d,d,d}", "up{u,u,u,u}", "back{b,b,b,b}", "front{f,f,f,f}",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn turns_scramble_into_target() {
+    let output = run(&["--scramble=R U", "--target=R U R", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "R\n(1 HTM, 1 QTM)\n");
+}
+
+#[test]
+fn restricted_solving() {
+    let output = run(&["--generators=R", "--turns=clockwise", "--scramble=R", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "R R R\n(3 HTM, 3 QTM)\n");
+
+    let output = run(&["--generators=RU", "--scramble=R U F", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("Unreachable state"));
+}
+
+#[test]
+fn solves_with_built_table() {
+    let path = std::env::temp_dir().join(format!("rubik2x2-test-{}.table", std::process::id()));
+    let path_argument = path.to_str().unwrap();
+    assert_eq!(run(&["build-table", path_argument]).status.code(), Some(0));
+
+    let table_argument = format!("--table={}", path_argument);
+    let output = run(&[&table_argument, "--scramble=R U F'", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "F U' R'\n(3 HTM, 3 QTM)\n");
+
+    // The table is for the half-turn metric.
+    let output = run(&[&table_argument, "--metric=quarter-turn", "--scramble=R U F'", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(5));
+
+    // Flip a bit of the entries.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[1000] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let output = run(&[&table_argument, "--scramble=R U F'", "--output=plain"]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("corrupted"));
+}
+
+#[test]
+fn compares_cheapest_and_shortest_solutions() {
+    let path = std::env::temp_dir().join(format!("rubik2x2-test-{}.costs", std::process::id()));
+    std::fs::write(&path, "# Front turns are slow\nF 5\nF' 5\nF2 5\n").unwrap();
+    let costs_argument = format!("--costs={}", path.to_str().unwrap());
+    let output = run(&[&costs_argument, "--scramble=F", "--output=csv"]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "solution,length,cost,moves\ncheapest,1,1,B'\nshortest,1,5,F'\n"
+    );
+}
+
+#[test]
+fn solves_in_ortega_steps() {
+    let output = run(&["--solver=ortega", "--scramble=R U R' U R U2 R'", "--output=plain"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let step_lines: Vec<&str> = stdout.lines().step_by(2).collect();
+    assert_eq!(step_lines, ["Face: ", "OLL (Antisune): R U2 R' U' R U' R'", "PBL (skip): "]);
+}
+
+#[test]
+fn explains_beginner_steps() {
+    let output = run(&["--solver=beginner", "--scramble=R U R' U'", "--output=json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\"steps\": [{\"name\": \"Holding\", \"explanation\": \""));
+    // The whole cube is turned to hold the first layer down, and then turned to insert each corner at the front
+    // right.
+    assert!(stdout.contains("\"moves\": [\"z'\"]}, {\"name\": \"First layer (left-up-back corner, left sticker \
+                             facing right)\""));
+    assert!(stdout.contains("\"moves\": [\"y2\", \"R\", \"U\", \"R'\"]}"));
+    assert!(stdout.contains("\"name\": \"Last layer permutation ("));
+}
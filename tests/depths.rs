@@ -0,0 +1,15 @@
+extern crate rubik2x2;
+use rubik2x2::distances;
+use rubik2x2::optimal::Metric;
+
+// This searches every state of the whole cube, so it only runs when asked for, with
+// 'cargo test --release -- --ignored'.
+#[test]
+#[ignore]
+fn whole_cube_depths_reach_every_state() {
+    for &(metric, max_depth) in [(Metric::HalfTurn, 11), (Metric::QuarterTurn, 14)].iter() {
+        let counts = distances::depth_counts(metric, false);
+        assert_eq!(counts.iter().sum::<usize>(), 88179840);
+        assert_eq!(counts.len() - 1, max_depth);
+    }
+}
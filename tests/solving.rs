@@ -0,0 +1,70 @@
+extern crate rubik2x2;
+use rubik2x2::cube;
+use rubik2x2::optimal;
+use rubik2x2::random::Random;
+use rubik2x2::scramble;
+use rubik2x2::solver;
+use rubik2x2::ui;
+
+// Random states, each seen from a different whole-cube rotation.
+fn scrambled_cubes(count: usize) -> Vec<(cube::Macro, cube::Cube)> {
+    let mut random = Random::new(16);
+    let mut cubes = Vec::new();
+    for idx in 0..count {
+        let scramble = scramble::random_state_scramble(&mut random, scramble::WCA_MINIMUM_LENGTH);
+        let rotation = cube::Rotation::from((idx % 9) as u8);
+        let cube = cube::Cube::solved().rotate(rotation).sequence_moves(scramble.move_iter());
+        cubes.push((scramble, cube));
+    }
+    cubes
+}
+
+#[test]
+fn macro_solver_solves_random_scrambles() {
+    for (_, cube) in scrambled_cubes(20) {
+        assert!(cube.sequence_moves(solver::solution(&cube).iter()).is_solved());
+    }
+}
+
+#[test]
+fn optimal_solver_solves_random_scrambles() {
+    for (scramble, cube) in scrambled_cubes(20) {
+        let moves = optimal::solution(&cube);
+        assert!(cube.sequence_moves(moves.iter()).is_solved());
+        // The scrambles are themselves as short as possible.
+        assert_eq!(moves.len(), scramble.moves.len());
+
+        let quarter_turn_moves = optimal::metric_solution(&cube, optimal::Metric::QuarterTurn);
+        assert!(cube.sequence_moves(quarter_turn_moves.iter()).is_solved());
+        assert!(quarter_turn_moves.len() <= 14);
+        assert!(
+            optimal::Metric::QuarterTurn.length(&quarter_turn_moves) <=
+            optimal::Metric::QuarterTurn.length(&moves)
+        );
+    }
+}
+
+#[test]
+fn viewer_pages_through_solutions_to_the_solved_cube() {
+    for (_, cube) in scrambled_cubes(5) {
+        let sections = ui::pages_of_moves(&optimal::solution(&cube));
+        let mut console = ui::ScriptedConsole::new(&vec![ui::Input::Forward; sections.len()]);
+        ui::run_main_loop(&ui::SideAliases::side_initials(), &cube, &mut console, &sections, 0);
+
+        let last_frame = console.frames.last().unwrap();
+        assert!(last_frame.starts_with(&format!("Progress: {}/{}\n", sections.len(), sections.len())));
+        // The diagram of the cube after the last page, with a letter for each sticker, ends 4 lines before the keys.
+        let lines: Vec<&str> = last_frame.lines().collect();
+        let keys_line_idx = lines.iter().position(|&line| line == "Left key: previous page").unwrap();
+        let rows: Vec<Vec<char>> =
+            lines[keys_line_idx - 10..keys_line_idx - 4].iter().map(|line| line.chars().step_by(2).collect()).collect();
+        // The cube may be rotated, but each side has stickers of one color.
+        for &(row_idx, column_idx) in [(0, 2), (2, 0), (2, 2), (2, 4), (2, 6), (4, 2)].iter() {
+            let sticker = rows[row_idx][column_idx];
+            assert!(sticker != ' ');
+            assert_eq!(rows[row_idx][column_idx + 1], sticker);
+            assert_eq!(rows[row_idx + 1][column_idx], sticker);
+            assert_eq!(rows[row_idx + 1][column_idx + 1], sticker);
+        }
+    }
+}
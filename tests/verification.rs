@@ -0,0 +1,13 @@
+extern crate rubik2x2;
+use rubik2x2::verification;
+
+// This takes minutes, so it only runs when asked for, with 'cargo test --release -- --ignored'.
+#[test]
+#[ignore]
+fn macro_solver_solves_every_state() {
+    let thread_count = std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+    match verification::verify_macro_solver(thread_count) {
+        Ok(report) => assert_eq!(report.state_count, 88179840),
+        Err(failure) => panic!("{}", failure.message()),
+    }
+}